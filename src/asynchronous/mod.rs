@@ -1,4 +1,5 @@
 pub mod channel;
+pub mod pull;
 pub mod traits;
 
 #[cfg(feature = "async-tokio")]
@@ -6,3 +7,5 @@ pub mod tokio;
 
 #[cfg(feature = "async-tokio")]
 pub use tokio::TokioSuck;
+
+pub use pull::{AsyncFallbackSucker, AsyncMappedSucker, AsyncPull, AsyncZip, async_zip};
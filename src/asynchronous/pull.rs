@@ -0,0 +1,618 @@
+use std::fmt::Display;
+use std::mem::Discriminant;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::asynchronous::channel::AsyncSucker;
+use crate::asynchronous::traits::{AsyncChannelReceiver, AsyncChannelSender};
+use crate::error::Error;
+use crate::types::{Request, Response, Ticket};
+
+/// The consumer side of an async channel, or a projection of one
+///
+/// The trait is sealed: it is implemented by [AsyncSucker] and the adaptors
+/// built on it, and cannot be implemented outside this crate.
+#[async_trait]
+pub trait AsyncPull: sealed::Sealed + Send + Sync {
+    type Item: Send;
+
+    /// Get the current value from the producer
+    async fn get(&self) -> Result<Self::Item, Error>;
+
+    /// Get the current value from the producer, giving up after `timeout`
+    async fn get_timeout(&self, timeout: Duration) -> Result<Self::Item, Error>;
+
+    /// Check if the channel is closed
+    async fn is_closed(&self) -> bool;
+
+    /// Close the channel from the consumer side
+    async fn close(&self) -> Result<(), Error>;
+
+    /// Get the current value, or `default` if the pull fails
+    async fn get_or(&self, default: Self::Item) -> Self::Item {
+        self.get().await.unwrap_or(default)
+    }
+
+    /// Get the current value, or compute one from the error if the pull fails
+    async fn get_or_else<F>(&self, f: F) -> Self::Item
+    where
+        F: FnOnce(Error) -> Self::Item + Send,
+    {
+        self.get().await.unwrap_or_else(f)
+    }
+
+    /// Send a request without waiting for its response, see [AsyncZip],
+    /// giving up after `timeout` or the default timeout should the queue
+    /// stay full
+    #[doc(hidden)]
+    async fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error>;
+
+    /// Wait for the response to a [request](Self::request), giving up after
+    /// `timeout` or the default timeout if there is none
+    #[doc(hidden)]
+    async fn receive(&self, ticket: Ticket, timeout: Option<Duration>)
+    -> Result<Self::Item, Error>;
+
+    /// Give up on a [request](Self::request) without waiting for its response
+    #[doc(hidden)]
+    fn abandon(&self, ticket: Ticket);
+
+    /// Convert every value pulled through this sucker with `f`
+    fn map<U, F>(
+        self,
+        f: F,
+    ) -> AsyncMappedSucker<Self, impl Fn(Self::Item) -> Result<U, Error> + Send + Sync>
+    where
+        Self: Sized,
+        U: Send,
+        F: Fn(Self::Item) -> U + Send + Sync,
+    {
+        AsyncMappedSucker::new(self, move |value| Ok(f(value)))
+    }
+
+    /// Convert every value pulled through this sucker with `f`, failing the
+    /// pull with [Error::MapFailed] when `f` does
+    fn try_map<U, E, F>(
+        self,
+        f: F,
+    ) -> AsyncMappedSucker<Self, impl Fn(Self::Item) -> Result<U, Error> + Send + Sync>
+    where
+        Self: Sized,
+        U: Send,
+        E: Display,
+        F: Fn(Self::Item) -> Result<U, E> + Send + Sync,
+    {
+        AsyncMappedSucker::new(self, move |value| {
+            f(value).map_err(|e| Error::MapFailed(e.to_string()))
+        })
+    }
+
+    /// Pull from `other` when a pull through this sucker fails with
+    /// [Error::NoSource] or [Error::ProducerDisconnected], see
+    /// [AsyncFallbackSucker::fallback_on] for other errors
+    fn or<S>(self, other: S) -> AsyncFallbackSucker<Self, S>
+    where
+        Self: Sized,
+        S: AsyncPull<Item = Self::Item>,
+    {
+        AsyncFallbackSucker::new(self, other)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+impl<T, ST, SR> sealed::Sealed for AsyncSucker<T, ST, SR>
+where
+    ST: AsyncChannelSender<Request>,
+    SR: AsyncChannelReceiver<Response<T>>,
+{
+}
+
+#[async_trait]
+impl<T, ST, SR> AsyncPull for AsyncSucker<T, ST, SR>
+where
+    T: Send + Sync,
+    ST: AsyncChannelSender<Request> + Sync,
+    SR: AsyncChannelReceiver<Response<T>> + Sync,
+{
+    type Item = T;
+
+    async fn get(&self) -> Result<T, Error> {
+        AsyncSucker::get(self).await
+    }
+
+    async fn get_timeout(&self, timeout: Duration) -> Result<T, Error> {
+        AsyncSucker::get_timeout(self, timeout).await
+    }
+
+    async fn is_closed(&self) -> bool {
+        AsyncSucker::is_closed(self).await
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        AsyncSucker::close(self).await
+    }
+
+    async fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        self.send_request(timeout).await.map(Ticket::Request)
+    }
+
+    async fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<T, Error> {
+        match ticket {
+            Ticket::Request(id) => {
+                self.recv_value(id, timeout.or(self.default_timeout()))
+                    .await
+            }
+            _ => Err(Error::InternalError),
+        }
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        if let Ticket::Request(id) = ticket {
+            AsyncSucker::abandon(self, id)
+        }
+    }
+}
+
+impl<P: AsyncPull + ?Sized> sealed::Sealed for &P {}
+
+#[async_trait]
+impl<P> AsyncPull for &P
+where
+    P: AsyncPull + ?Sized,
+{
+    type Item = P::Item;
+
+    async fn get(&self) -> Result<P::Item, Error> {
+        (**self).get().await
+    }
+
+    async fn get_timeout(&self, timeout: Duration) -> Result<P::Item, Error> {
+        (**self).get_timeout(timeout).await
+    }
+
+    async fn is_closed(&self) -> bool {
+        (**self).is_closed().await
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        (**self).close().await
+    }
+
+    async fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        (**self).request(timeout).await
+    }
+
+    async fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<P::Item, Error> {
+        (**self).receive(ticket, timeout).await
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        (**self).abandon(ticket)
+    }
+}
+
+/// An async sucker whose values are converted on the way out, see
+/// [AsyncPull::map]
+pub struct AsyncMappedSucker<S, F> {
+    inner: S,
+    f: F,
+}
+
+impl<S, F> AsyncMappedSucker<S, F> {
+    fn new(inner: S, f: F) -> Self {
+        Self { inner, f }
+    }
+
+    /// The sucker values are pulled through
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Give back the sucker values are pulled through
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, F> sealed::Sealed for AsyncMappedSucker<S, F> {}
+
+#[async_trait]
+impl<S, F, U> AsyncPull for AsyncMappedSucker<S, F>
+where
+    S: AsyncPull,
+    U: Send,
+    F: Fn(S::Item) -> Result<U, Error> + Send + Sync,
+{
+    type Item = U;
+
+    async fn get(&self) -> Result<U, Error> {
+        self.inner.get().await.and_then(&self.f)
+    }
+
+    async fn get_timeout(&self, timeout: Duration) -> Result<U, Error> {
+        self.inner.get_timeout(timeout).await.and_then(&self.f)
+    }
+
+    async fn is_closed(&self) -> bool {
+        self.inner.is_closed().await
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        self.inner.close().await
+    }
+
+    async fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        self.inner.request(timeout).await
+    }
+
+    async fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<U, Error> {
+        self.inner.receive(ticket, timeout).await.and_then(&self.f)
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        self.inner.abandon(ticket)
+    }
+}
+
+/// An async sucker that falls back to another when a pull fails, see
+/// [AsyncPull::or]
+pub struct AsyncFallbackSucker<P, S> {
+    primary: P,
+    secondary: S,
+    triggers: Vec<Discriminant<Error>>,
+}
+
+impl<P, S> AsyncFallbackSucker<P, S> {
+    fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            triggers: Vec::new(),
+        }
+        .fallback_on([Error::NoSource, Error::ProducerDisconnected])
+    }
+
+    /// Fall back on these errors instead, compared by variant only, so
+    /// `Error::SourceFailed(String::new())` matches any failed source
+    pub fn fallback_on(mut self, errors: impl IntoIterator<Item = Error>) -> Self {
+        self.triggers = errors
+            .into_iter()
+            .map(|error| std::mem::discriminant(&error))
+            .collect();
+        self
+    }
+
+    fn falls_back(&self, error: &Error) -> bool {
+        self.triggers.contains(&std::mem::discriminant(error))
+    }
+
+    /// Give back both suckers
+    pub fn into_inner(self) -> (P, S) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl<P, S> sealed::Sealed for AsyncFallbackSucker<P, S> {}
+
+#[async_trait]
+impl<P, S> AsyncPull for AsyncFallbackSucker<P, S>
+where
+    P: AsyncPull,
+    S: AsyncPull<Item = P::Item>,
+{
+    type Item = P::Item;
+
+    async fn get(&self) -> Result<P::Item, Error> {
+        let ticket = self.request(None).await?;
+        self.receive(ticket, None).await
+    }
+
+    async fn get_timeout(&self, timeout: Duration) -> Result<P::Item, Error> {
+        let deadline = Instant::now() + timeout;
+        let ticket = self.request(Some(timeout)).await?;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        self.receive(ticket, Some(remaining)).await
+    }
+
+    async fn is_closed(&self) -> bool {
+        self.primary.is_closed().await && self.secondary.is_closed().await
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        let primary = self.primary.close().await;
+        let secondary = self.secondary.close().await;
+        primary.and(secondary)
+    }
+
+    async fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        match self.primary.request(timeout).await {
+            Err(error) if self.falls_back(&error) => {
+                let ticket = self.secondary.request(timeout).await?;
+                Ok(Ticket::Diverted(Box::new(ticket)))
+            }
+            result => result,
+        }
+    }
+
+    async fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<P::Item, Error> {
+        if let Ticket::Diverted(ticket) = ticket {
+            return self.secondary.receive(*ticket, timeout).await;
+        }
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        match self.primary.receive(ticket, timeout).await {
+            Err(error) if self.falls_back(&error) => match deadline {
+                Some(deadline) => {
+                    self.secondary
+                        .get_timeout(deadline.saturating_duration_since(Instant::now()))
+                        .await
+                }
+                None => self.secondary.get().await,
+            },
+            result => result,
+        }
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        match ticket {
+            Ticket::Diverted(ticket) => self.secondary.abandon(*ticket),
+            ticket => self.primary.abandon(ticket),
+        }
+    }
+}
+
+/// Pull from two async suckers at once, see [AsyncZip]
+pub fn async_zip<A: AsyncPull, B: AsyncPull>(a: A, b: B) -> AsyncZip<(A, B)> {
+    AsyncZip::new((a, b))
+}
+
+/// Pull from every async sucker in a tuple of two to five at once
+///
+/// All requests are sent before waiting on any response, so the producers
+/// work concurrently and a pull takes as long as the slowest of them.
+/// Dropping the pull before it completes leaves no response behind.
+pub struct AsyncZip<S>(S);
+
+impl<S> AsyncZip<S> {
+    /// Zip a tuple of two to five async suckers
+    pub fn new(suckers: S) -> Self {
+        Self(suckers)
+    }
+
+    /// Give back the suckers
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+/// Pull from several async suckers at once, returning a tuple of their values
+///
+/// Takes two to five suckers by reference, see [AsyncZip].
+#[macro_export]
+macro_rules! async_join {
+    ($($sucker:expr),+ $(,)?) => {
+        $crate::AsyncPull::get(&$crate::AsyncZip::new(($(&$sucker,)+)))
+    };
+}
+
+/// The part of [AsyncPull] needed to give up on responses not yet received
+trait Outstanding: Sync {
+    fn abandon_request(&self, ticket: Ticket);
+}
+
+impl<P: AsyncPull> Outstanding for P {
+    fn abandon_request(&self, ticket: Ticket) {
+        self.abandon(ticket)
+    }
+}
+
+/// Abandons the requests it still holds when dropped
+struct Abandon<'a, const N: usize>([Option<(&'a dyn Outstanding, Ticket)>; N]);
+
+impl<const N: usize> Abandon<'_, N> {
+    fn new() -> Self {
+        Self(std::array::from_fn(|_| None))
+    }
+
+    /// Take back the tickets, which are no longer abandoned
+    fn keep(&mut self) -> Vec<Ticket> {
+        self.0
+            .iter_mut()
+            .filter_map(Option::take)
+            .map(|(_, ticket)| ticket)
+            .collect()
+    }
+}
+
+impl<const N: usize> Drop for Abandon<'_, N> {
+    fn drop(&mut self) {
+        for (sucker, ticket) in self.0.iter_mut().filter_map(Option::take) {
+            sucker.abandon_request(ticket);
+        }
+    }
+}
+
+impl<S> sealed::Sealed for AsyncZip<S> {}
+
+macro_rules! impl_async_zip {
+    ($len:literal; $($name:ident $index:tt),+) => {
+        #[async_trait]
+        impl<$($name: AsyncPull),+> AsyncPull for AsyncZip<($($name,)+)> {
+            type Item = ($($name::Item,)+);
+
+            async fn get(&self) -> Result<Self::Item, Error> {
+                let ticket = self.request(None).await?;
+                self.receive(ticket, None).await
+            }
+
+            async fn get_timeout(&self, timeout: Duration) -> Result<Self::Item, Error> {
+                let deadline = Instant::now() + timeout;
+                let ticket = self.request(Some(timeout)).await?;
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                self.receive(ticket, Some(remaining)).await
+            }
+
+            async fn is_closed(&self) -> bool {
+                $(self.0.$index.is_closed().await)||+
+            }
+
+            async fn close(&self) -> Result<(), Error> {
+                let results = ($(self.0.$index.close().await,)+);
+                $(results.$index?;)+
+                Ok(())
+            }
+
+            async fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                let remaining =
+                    || deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+                let mut sent = Abandon::<$len>::new();
+                $(
+                    let ticket = self.0.$index.request(remaining()).await?;
+                    sent.0[$index] = Some((&self.0.$index, ticket));
+                )+
+                Ok(Ticket::Many(sent.keep()))
+            }
+
+            async fn receive(
+                &self,
+                ticket: Ticket,
+                timeout: Option<Duration>,
+            ) -> Result<Self::Item, Error> {
+                let Ticket::Many(tickets) = ticket else {
+                    return Err(Error::InternalError);
+                };
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                let remaining =
+                    || deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+                // Each sucker takes care of its own response once it starts
+                // receiving, the rest are abandoned if this is dropped
+                let mut pending = Abandon::<$len>::new();
+                let mut tickets = tickets.into_iter();
+                $(pending.0[$index] = tickets.next().map(|ticket| (&self.0.$index as _, ticket));)+
+                let results = ($(
+                    match pending.0[$index].take() {
+                        Some((_, ticket)) => self.0.$index.receive(ticket, remaining()).await,
+                        None => Err(Error::InternalError),
+                    },
+                )+);
+                Ok(($(results.$index?,)+))
+            }
+
+            fn abandon(&self, ticket: Ticket) {
+                if let Ticket::Many(tickets) = ticket {
+                    let mut tickets = tickets.into_iter();
+                    $(if let Some(ticket) = tickets.next() {
+                        self.0.$index.abandon(ticket);
+                    })+
+                }
+            }
+        }
+    };
+}
+
+impl_async_zip!(2; A 0, B 1);
+impl_async_zip!(3; A 0, B 1, C 2);
+impl_async_zip!(4; A 0, B 1, C 2, D 3);
+impl_async_zip!(5; A 0, B 1, C 2, D 3, E 4);
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::asynchronous::tokio::TokioSuck;
+
+    async fn doubled<P: AsyncPull<Item = i32>>(sucker: &P) -> Result<i32, Error> {
+        sucker.get().await.map(|n| n * 2)
+    }
+
+    #[tokio::test]
+    async fn test_map_and_try_map() {
+        let (sucker, sourcer) = TokioSuck::<&'static str>::pair();
+        let sucker = sucker.try_map(str::parse::<i32>).map(|n| n + 1);
+
+        let mut values = ["20", "twenty"].into_iter();
+        sourcer.set_mut(move || values.next().unwrap()).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        // A projection works wherever a sucker does
+        assert_eq!(doubled(&sucker).await, Ok(42));
+        assert_eq!(
+            sucker.get().await,
+            Err(Error::MapFailed("invalid digit found in string".to_owned()))
+        );
+
+        sucker.close().await.unwrap();
+        assert_eq!(sucker.get().await, Err(Error::ChannelClosed));
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_async_join() {
+        let mut suckers = Vec::new();
+        let mut producers = Vec::new();
+        for n in 0..3 {
+            let (sucker, sourcer) = TokioSuck::<i32>::pair();
+            sourcer
+                .set(move || {
+                    std::thread::sleep(Duration::from_millis(100));
+                    n
+                })
+                .unwrap();
+            producers.push(tokio::spawn(sourcer.run()));
+            suckers.push(sucker);
+        }
+        let [a, b, c] = <[_; 3]>::try_from(suckers).ok().unwrap();
+
+        let start = Instant::now();
+        assert_eq!(crate::async_join!(a, b, c).await, Ok((0, 1, 2)));
+        assert!(start.elapsed() < Duration::from_millis(250));
+
+        // A dropped pull leaves nothing behind for the next one
+        let pull = async_zip(&a, &b);
+        let result = tokio::time::timeout(Duration::from_millis(10), pull.get()).await;
+        assert!(result.is_err());
+        assert_eq!(async_zip(&a, &c).get().await, Ok((0, 2)));
+        assert_eq!(b.get().await, Ok(1));
+
+        async_zip(a, b).close().await.unwrap();
+        c.close().await.unwrap();
+        for producer in producers {
+            producer.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_or_falls_back() {
+        let (primary, primary_source) = TokioSuck::<i32>::pair();
+        let (secondary, secondary_source) = TokioSuck::<i32>::pair();
+        secondary_source.set_static(2).unwrap();
+        let producers = [
+            tokio::spawn(primary_source.run()),
+            tokio::spawn(secondary_source.run()),
+        ];
+
+        let sucker = (&primary).or(&secondary);
+        assert_eq!(sucker.get().await, Ok(2));
+
+        // Only the chosen errors trigger the fallback
+        let sucker = sucker.fallback_on([Error::ChannelClosed]);
+        assert_eq!(sucker.get().await, Err(Error::NoSource));
+        primary.close().await.unwrap();
+        assert_eq!(sucker.get().await, Ok(2));
+
+        assert_eq!(primary.get_or(1).await, 1);
+        assert_eq!(primary.get_or_else(|_| 1).await, 1);
+        assert_eq!(secondary.get_or(1).await, 2);
+
+        secondary.close().await.unwrap();
+        for producer in producers {
+            producer.await.unwrap().unwrap();
+        }
+    }
+}
@@ -1,34 +1,53 @@
-use std::sync::Arc;
+use std::time::Duration;
 
-use arc_swap::ArcSwap;
 use async_trait::async_trait;
-use tokio::sync::{Mutex, mpsc};
+use tokio::sync::{Mutex, mpsc, watch};
 
 use crate::asynchronous::traits::{
-    AsyncChannelReceiver, AsyncChannelSender, AsyncChannelType, ChannelError,
+    AsyncChannelReceiver, AsyncChannelSender, AsyncChannelType, AsyncPushReceiver, ChannelError,
 };
 use crate::types;
 
-type TokioSucker<T> = crate::asynchronous::channel::AsyncSucker<
-    T,
-    TokioSender<types::Request>,
-    TokioReceiver<types::Response<T>>,
->;
-type TokioSourcer<T> = crate::asynchronous::channel::AsyncSourcer<
-    T,
-    TokioReceiver<types::Request>,
-    TokioSender<types::Response<T>>,
->;
+type TokioSucker<T> = crate::asynchronous::channel::AsyncSuckerFor<TokioChannel, T>;
+type TokioSourcer<T> = crate::asynchronous::channel::AsyncSourcerFor<TokioChannel, T>;
 
-pub struct TokioSender<T>(mpsc::UnboundedSender<T>);
-pub struct TokioReceiver<T>(Mutex<mpsc::UnboundedReceiver<T>>);
+pub struct TokioSender<T>(TokioSenderKind<T>);
+pub struct TokioReceiver<T>(Mutex<TokioReceiverKind<T>>);
+
+enum TokioSenderKind<T> {
+    Unbounded(mpsc::UnboundedSender<T>),
+    Bounded(mpsc::Sender<T>),
+}
+
+enum TokioReceiverKind<T> {
+    Unbounded(mpsc::UnboundedReceiver<T>),
+    Bounded(mpsc::Receiver<T>),
+}
 
 #[async_trait]
 impl<T: Send + 'static> AsyncChannelSender<T> for TokioSender<T> {
     async fn send(&self, msg: T) -> Result<(), ChannelError> {
-        self.0
-            .send(msg)
-            .map_err(|_| ChannelError::ProducerDisconnected)
+        match &self.0 {
+            TokioSenderKind::Unbounded(tx) => {
+                tx.send(msg).map_err(|_| ChannelError::ProducerDisconnected)
+            }
+            TokioSenderKind::Bounded(tx) => tx
+                .send(msg)
+                .await
+                .map_err(|_| ChannelError::ProducerDisconnected),
+        }
+    }
+
+    fn try_send(&self, msg: T) -> Result<(), ChannelError> {
+        match &self.0 {
+            TokioSenderKind::Unbounded(tx) => {
+                tx.send(msg).map_err(|_| ChannelError::ProducerDisconnected)
+            }
+            TokioSenderKind::Bounded(tx) => tx.try_send(msg).map_err(|e| match e {
+                mpsc::error::TrySendError::Full(_) => ChannelError::Busy,
+                _ => ChannelError::ProducerDisconnected,
+            }),
+        }
     }
 }
 
@@ -36,10 +55,57 @@ impl<T: Send + 'static> AsyncChannelSender<T> for TokioSender<T> {
 impl<T: Send + 'static> AsyncChannelReceiver<T> for TokioReceiver<T> {
     async fn recv(&self) -> Result<T, ChannelError> {
         let mut receiver = self.0.lock().await;
-        receiver
-            .recv()
+        match &mut *receiver {
+            TokioReceiverKind::Unbounded(rx) => rx.recv().await,
+            TokioReceiverKind::Bounded(rx) => rx.recv().await,
+        }
+        .ok_or(ChannelError::ProducerDisconnected)
+    }
+
+    async fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError> {
+        tokio::time::timeout(timeout, self.recv())
             .await
-            .ok_or(ChannelError::ProducerDisconnected)
+            .unwrap_or(Err(ChannelError::Timeout))
+    }
+
+    fn try_recv(&self) -> Result<Option<T>, ChannelError> {
+        // Someone else is receiving, so there is nothing for us
+        let Ok(mut receiver) = self.0.try_lock() else {
+            return Ok(None);
+        };
+        match &mut *receiver {
+            TokioReceiverKind::Unbounded(rx) => rx.try_recv(),
+            TokioReceiverKind::Bounded(rx) => rx.try_recv(),
+        }
+        .map(Some)
+        .or_else(|e| match e {
+            mpsc::error::TryRecvError::Empty => Ok(None),
+            mpsc::error::TryRecvError::Disconnected => Err(ChannelError::ProducerDisconnected),
+        })
+    }
+}
+
+#[async_trait]
+impl<T: Send> AsyncPushReceiver<T> for mpsc::Receiver<T> {
+    async fn recv_next(&mut self) -> Option<T> {
+        self.recv().await
+    }
+}
+
+#[async_trait]
+impl<T: Send> AsyncPushReceiver<T> for mpsc::UnboundedReceiver<T> {
+    async fn recv_next(&mut self) -> Option<T> {
+        self.recv().await
+    }
+}
+
+/// Yields each value sent after the one last seen, so call `mark_changed`
+/// first to start from the current one
+#[async_trait]
+impl<T: Clone + Send + Sync> AsyncPushReceiver<T> for watch::Receiver<T> {
+    async fn recv_next(&mut self) -> Option<T> {
+        self.changed().await.ok()?;
+        Some(self.borrow_and_update().clone())
     }
 }
 
@@ -50,19 +116,39 @@ impl AsyncChannelType for TokioChannel {
     type Receiver<T: Send + 'static> = TokioReceiver<T>;
 
     fn create_request_channel() -> (Self::Sender<types::Request>, Self::Receiver<types::Request>) {
-        let (tx, rx) = mpsc::unbounded_channel();
-        (TokioSender(tx), TokioReceiver(Mutex::new(rx)))
+        unbounded()
     }
 
     fn create_response_channel<T: Send + 'static>() -> (
         Self::Sender<types::Response<T>>,
         Self::Receiver<types::Response<T>>,
     ) {
-        let (tx, rx) = mpsc::unbounded_channel();
-        (TokioSender(tx), TokioReceiver(Mutex::new(rx)))
+        unbounded()
+    }
+
+    fn create_bounded_request_channel(
+        capacity: usize,
+    ) -> (Self::Sender<types::Request>, Self::Receiver<types::Request>) {
+        bounded(capacity)
     }
 }
 
+fn unbounded<T>() -> (TokioSender<T>, TokioReceiver<T>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (
+        TokioSender(TokioSenderKind::Unbounded(tx)),
+        TokioReceiver(Mutex::new(TokioReceiverKind::Unbounded(rx))),
+    )
+}
+
+fn bounded<T>(capacity: usize) -> (TokioSender<T>, TokioReceiver<T>) {
+    let (tx, rx) = mpsc::channel(capacity);
+    (
+        TokioSender(TokioSenderKind::Bounded(tx)),
+        TokioReceiver(Mutex::new(TokioReceiverKind::Bounded(rx))),
+    )
+}
+
 pub struct TokioSuck<T> {
     _phantom: std::marker::PhantomData<T>,
 }
@@ -70,17 +156,16 @@ pub struct TokioSuck<T> {
 impl<T> TokioSuck<T> {
     pub fn pair() -> (TokioSucker<T>, TokioSourcer<T>)
     where
-        T: Clone + Send + 'static,
+        T: Send + 'static,
     {
-        let (request_tx, request_rx) = TokioChannel::create_request_channel();
-        let (response_tx, response_rx) = TokioChannel::create_response_channel::<T>();
-        let state = ArcSwap::new(Arc::new(crate::types::ValueSource::None));
-
-        let sucker = crate::asynchronous::channel::AsyncSucker::new(request_tx, response_rx);
-        let sourcer =
-            crate::asynchronous::channel::AsyncSourcer::new(request_rx, response_tx, state);
+        crate::asynchronous::channel::async_pair::<TokioChannel, T>()
+    }
 
-        (sucker, sourcer)
+    pub fn pair_bounded(capacity: usize) -> (TokioSucker<T>, TokioSourcer<T>)
+    where
+        T: Send + 'static,
+    {
+        crate::asynchronous::channel::async_pair_bounded::<TokioChannel, T>(capacity)
     }
 }
 
@@ -89,6 +174,22 @@ mod tests {
     use super::*;
     use crate::Error;
 
+    crate::testing::async_backend_conformance!(TokioChannel);
+
+    /// A stream yielding whatever arrives on a channel
+    struct Arrivals(mpsc::UnboundedReceiver<u32>);
+
+    impl futures_core::Stream for Arrivals {
+        type Item = u32;
+
+        fn poll_next(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<u32>> {
+            self.0.poll_recv(cx)
+        }
+    }
+
     #[tokio::test]
     async fn test_pre_computed_value() {
         let (sucker, sourcer) = TokioSuck::<i32>::pair();
@@ -117,4 +218,734 @@ mod tests {
         sucker.close().await.unwrap();
         producer.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_bounded_overflow_fail() {
+        let (sucker, sourcer) = TokioSuck::<i32>::pair_bounded(1);
+        let sucker = sucker.with_overflow(crate::OverflowPolicy::Fail);
+
+        let result = sucker.get_timeout(Duration::from_millis(10)).await;
+        assert!(matches!(result, Err(Error::Timeout)));
+
+        let result = sucker.get().await;
+        assert!(matches!(result, Err(Error::Busy)));
+
+        let producer = tokio::spawn(async move {
+            sourcer.set_static(42).unwrap();
+            sourcer.run().await.unwrap();
+        });
+
+        // Once the producer has drained the queue there is room again
+        let result = loop {
+            match sucker.get().await {
+                Err(Error::Busy) => tokio::task::yield_now().await,
+                result => break result,
+            }
+        };
+        assert_eq!(result, Ok(42));
+        sucker.close().await.unwrap();
+        producer.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_coalesced_requests_share_one_evaluation() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        const CONSUMERS: usize = 8;
+
+        let (sucker, sourcer) = TokioSuck::<usize>::pair();
+        let sourcer = sourcer.with_coalescing();
+        let sucker = Arc::new(sucker);
+        let evaluations = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&evaluations);
+        sourcer
+            .set(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                counter.fetch_add(1, Ordering::SeqCst) + 1
+            })
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let sucker = Arc::clone(&sucker);
+                tokio::spawn(async move { sucker.get().await.unwrap() })
+            })
+            .collect();
+
+        for consumer in consumers {
+            consumer.await.unwrap();
+        }
+
+        assert!(evaluations.load(Ordering::SeqCst) < CONSUMERS);
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failure_policy_retry() {
+        let (sucker, sourcer) = TokioSuck::<i32>::pair();
+        let sourcer = sourcer.with_failure_policy(crate::FailurePolicy::RetryN {
+            attempts: 1,
+            backoff: Duration::from_millis(1),
+        });
+
+        let mut calls = 0;
+        sourcer
+            .set_fallible_mut(move || {
+                calls += 1;
+                if calls % 3 == 0 {
+                    Ok(calls)
+                } else {
+                    Err("not yet")
+                }
+            })
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        // One retry isn't enough to get past two failures
+        let result = sucker.get().await;
+        assert_eq!(result, Err(Error::SourceFailed("not yet".to_owned())));
+
+        // The third call succeeds
+        assert_eq!(sucker.get().await, Ok(3));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_poisoned_source() {
+        let (sucker, sourcer) = TokioSuck::<i32>::pair();
+        let sourcer = sourcer.with_poison_policy(crate::PoisonPolicy::Fail);
+        sourcer.handle().install(crate::types::poisoned_mut(|| 42));
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Err(Error::SourcePoisoned));
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+
+        let (sucker, sourcer) = TokioSuck::<i32>::pair();
+        sourcer.handle().install(crate::types::poisoned_mut(|| 42));
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Ok(42));
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    /// A stream that panics the first time it is polled
+    struct PanicsOnce(bool);
+
+    impl futures_core::Stream for PanicsOnce {
+        type Item = u32;
+
+        fn poll_next(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<u32>> {
+            if !std::mem::replace(&mut self.0, true) {
+                panic!("intentional panic from stream source");
+            }
+            std::task::Poll::Ready(Some(7))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_stream_poisons() {
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let sourcer = sourcer.with_poison_policy(crate::PoisonPolicy::Fail);
+        sourcer.set_stream(PanicsOnce(false)).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Err(Error::NoSource));
+        assert_eq!(sucker.get().await, Err(Error::SourcePoisoned));
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        sourcer.set_stream(PanicsOnce(false)).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Err(Error::NoSource));
+        assert_eq!(sucker.get().await, Ok(7));
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_wait_parks_until_source_set() {
+        let (sucker, sourcer) = TokioSuck::<i32>::pair();
+        let handle = sourcer.handle();
+        let producer = tokio::spawn(sourcer.run());
+
+        let timeout = Duration::from_millis(20);
+        assert_eq!(sucker.get_wait(Some(timeout)).await, Err(Error::Timeout));
+        assert_eq!(sucker.get().await, Err(Error::NoSource));
+
+        let setter = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            handle.set_static(42).unwrap();
+        });
+        assert_eq!(sucker.get_wait(None).await, Ok(42));
+        setter.await.unwrap();
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_next_is_cancel_safe() {
+        let (sucker, sourcer) = TokioSuck::<i32>::pair();
+        let handle = sourcer.handle();
+        sourcer.set_static(1).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        let (first, value) = sucker.get_next(crate::Generation::default()).await.unwrap();
+        assert_eq!(value, 1);
+
+        // A dropped long poll doesn't hand its response to the next pull
+        let poll = tokio::time::timeout(Duration::from_millis(20), sucker.get_next(first)).await;
+        assert!(poll.is_err());
+        assert_eq!(sucker.get().await, Ok(1));
+
+        let setter = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            handle.set_static(2).unwrap();
+        });
+        let (second, value) = sucker.get_next(first).await.unwrap();
+        assert_eq!(value, 2);
+        assert!(second > first);
+        setter.await.unwrap();
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_if_changed() {
+        let (sucker, sourcer) = TokioSuck::<i32>::pair();
+        let handle = sourcer.handle();
+        sourcer.set_static(1).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        let changed = sucker.get_if_changed(crate::Generation::default()).await;
+        let (generation, value) = changed.unwrap().unwrap();
+        assert_eq!(value, 1);
+        assert_eq!(sucker.get_if_changed(generation).await, Ok(None));
+
+        handle.set_static(2).unwrap();
+        let changed = sucker.get_if_changed(generation).await;
+        assert_eq!(changed.unwrap().map(|(_, value)| value), Some(2));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shared_sucker_gets_its_own_responses() {
+        let (sucker, sourcer) = TokioSuck::<i32>::pair();
+        let sourcer = sourcer.with_history(1);
+        sourcer
+            .set(|| {
+                std::thread::sleep(Duration::from_millis(1));
+                42
+            })
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        tokio::join!(
+            async {
+                for _ in 0..50 {
+                    assert_eq!(sucker.get().await, Ok(42));
+                }
+            },
+            async {
+                for _ in 0..50 {
+                    assert!(sucker.history(1).await.is_ok());
+                }
+            },
+            async {
+                for _ in 0..50 {
+                    let changed = sucker.get_if_changed(crate::Generation::default()).await;
+                    assert_eq!(changed.unwrap().map(|(_, value)| value), Some(42));
+                }
+            },
+            // Responses to requests given up on go to nobody
+            async {
+                for _ in 0..50 {
+                    let _ = tokio::time::timeout(Duration::from_micros(500), sucker.get()).await;
+                }
+            },
+        );
+        assert_eq!(sucker.get().await, Ok(42));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shared_source() {
+        use std::sync::Arc;
+
+        let (sucker, sourcer) = TokioSuck::<Vec<u8>>::pair();
+        let snapshot = Arc::new(vec![1, 2, 3]);
+        sourcer.set_shared(Arc::clone(&snapshot)).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert!(Arc::ptr_eq(&sucker.get_arc().await.unwrap(), &snapshot));
+        assert_eq!(sucker.get().await, Err(Error::SharedOnly));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_with() {
+        let (sucker, sourcer) = TokioSuck::<Vec<u8>>::pair();
+        sourcer.set_static(vec![1, 2, 3]).unwrap();
+        let handle = sourcer.handle();
+
+        // Fixed values are read without the producer
+        assert_eq!(sucker.with(|v| v.len()).await, Ok(3));
+
+        let producer = tokio::spawn(sourcer.run());
+        handle.set(|| vec![4]).unwrap();
+        assert_eq!(sucker.with(|v| v[0]).await, Ok(4));
+        assert_eq!(
+            sucker
+                .try_with(|v| v.get(1).copied().ok_or("too short"))
+                .await,
+            Err(Error::MapFailed("too short".to_string()))
+        );
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update() {
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        assert_eq!(sourcer.update(|n| n + 1), Err(Error::NoSource));
+        sourcer.set_static(1).unwrap();
+        let handle = sourcer.handle();
+        let producer = tokio::spawn(sourcer.run());
+
+        handle.update(|n| n * 10).unwrap();
+        assert_eq!(sucker.get().await, Ok(10));
+
+        let stale = handle.generation();
+        handle.update(|n| n + 1).unwrap();
+        assert_eq!(handle.compare_and_set(stale, 0), Err(Error::Conflict));
+        assert_eq!(sucker.get().await, Ok(11));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_history() {
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let sourcer = sourcer.with_history(2);
+        let counter = std::sync::atomic::AtomicU32::new(0);
+        sourcer
+            .set(move || counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        for _ in 0..3 {
+            sucker.get().await.unwrap();
+        }
+        let values: Vec<_> = sucker
+            .history(5)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|served| served.value)
+            .collect();
+        assert_eq!(values, vec![1, 2]);
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_stream() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        /// Counts down to one, yielding to the executor before each element
+        struct Countdown {
+            left: u32,
+            yielded: bool,
+        }
+
+        impl futures_core::Stream for Countdown {
+            type Item = u32;
+
+            fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u32>> {
+                if !std::mem::replace(&mut self.yielded, true) {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                self.yielded = false;
+                let left = self.left;
+                self.left = left.saturating_sub(1);
+                Poll::Ready((left > 0).then_some(left))
+            }
+        }
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        sourcer
+            .set_stream(Countdown {
+                left: 2,
+                yielded: false,
+            })
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Ok(2));
+        assert_eq!(sucker.get().await, Ok(1));
+        assert_eq!(sucker.get().await, Err(Error::ChannelClosed));
+        assert_eq!(sucker.get().await, Err(Error::ChannelClosed));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_evaluation_timeout() {
+        use crate::InFlight;
+        use std::time::Duration;
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let sourcer = sourcer.with_evaluation_timeout(Duration::from_millis(50), InFlight::Reuse);
+        let (tx, rx) = mpsc::unbounded_channel();
+        sourcer.set_stream(Arrivals(rx)).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Err(Error::SourceTimeout));
+        tx.send(5).unwrap();
+        assert_eq!(sucker.get().await, Ok(5));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_evaluation_timeout_leaves_runtime_free() {
+        use crate::InFlight;
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let sourcer = sourcer.with_evaluation_timeout(Duration::from_secs(1), InFlight::Reuse);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let rx = Mutex::new(rx);
+        sourcer
+            .set(move || rx.lock().unwrap().recv().unwrap())
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        // The closure blocks until this test's only worker thread gets to
+        // run again, which it does while the producer waits for the closure
+        let (value, ()) = tokio::join!(sucker.get(), async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            tx.send(3).unwrap();
+        });
+        assert_eq!(value, Ok(3));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_abandoned_stream() {
+        use std::time::Duration;
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let (tx, rx) = mpsc::unbounded_channel();
+        sourcer.set_stream(Arrivals(rx)).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        // Dropping the only pending get cancels polling the stream for it
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), sucker.get())
+                .await
+                .is_err()
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        tx.send(5).unwrap();
+        assert_eq!(sucker.get().await, Ok(5));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_abandoned_while_queue_full() {
+        use std::time::Duration;
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair_bounded(1);
+        let sucker = sucker.with_overflow(crate::OverflowPolicy::Block);
+        let (tx, rx) = mpsc::unbounded_channel();
+        sourcer.set_stream(Arrivals(rx)).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        // Gets dropped while waiting for room in the queue don't count as
+        // waiting, so polling the stream is cancelled as for the others
+        let gets = async { tokio::join!(sucker.get(), sucker.get(), sucker.get()) };
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), gets)
+                .await
+                .is_err()
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        tx.send(5).unwrap();
+        let get = tokio::time::timeout(Duration::from_secs(5), sucker.get()).await;
+        assert_eq!(get, Ok(Ok(5)));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pair_bounded_zero() {
+        let (sucker, sourcer) = TokioSuck::<u32>::pair_bounded(0);
+        sourcer.set_static(7).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Ok(7));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_latest_from() {
+        use std::time::Duration;
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let (tx, rx) = tokio::sync::watch::channel(0);
+        sourcer.set_latest_from(rx).unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        // Nothing was sent since the receiver was created
+        assert_eq!(sucker.get().await, Err(Error::NoSource));
+
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sucker.get().await != Ok(2) {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_latest_from_wait() {
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(7).unwrap();
+        sourcer.set_latest_from_wait(rx).await.unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Ok(7));
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<u32>(1);
+        drop(tx);
+        let (_, sourcer) = TokioSuck::<u32>::pair();
+        assert_eq!(
+            sourcer.set_latest_from_wait(rx).await,
+            Err(Error::ProducerDisconnected)
+        );
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_lazy() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let (sucker, sourcer) = TokioSuck::<usize>::pair();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&runs);
+        sourcer
+            .set_lazy(move || counted.fetch_add(1, Ordering::SeqCst) + 10)
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        for _ in 0..3 {
+            assert_eq!(sucker.get().await, Ok(10));
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(sucker.with(|n| *n).await, Ok(10));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        use crate::{LimitPolicy, RateLimit};
+        use std::time::Duration;
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let limit = RateLimit::MinSpacing(Duration::from_secs(60));
+        let sourcer = sourcer.with_rate_limit(limit, LimitPolicy::ServeLast);
+        // Nothing to serve yet when the first evaluation fails
+        sourcer.set_fallible(|| Err::<u32, _>("down")).unwrap();
+        let handle = sourcer.handle();
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(
+            sucker.get().await,
+            Err(Error::SourceFailed("down".to_string()))
+        );
+        assert_eq!(sucker.get().await, Err(Error::RateLimited));
+
+        handle.set(|| 3).unwrap();
+        assert_eq!(sucker.get().await, Err(Error::RateLimited));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_prefetch() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&runs);
+        sourcer
+            .set_prefetch(move || counted.fetch_add(1, Ordering::SeqCst) + 1, 2)
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        // Values are computed before anyone pulls, but no more than `depth`
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while runs.load(Ordering::SeqCst) < 2 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        for expected in 1..=5 {
+            assert_eq!(sucker.get().await, Ok(expected));
+        }
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_set_prefetch_without_runtime() {
+        let (_sucker, sourcer) = TokioSuck::<u32>::pair();
+        assert_eq!(sourcer.set_prefetch(|| 1, 2), Err(Error::NoRuntime));
+    }
+
+    #[tokio::test]
+    async fn test_set_refreshing() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::Duration;
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let runs = Arc::new(AtomicU32::new(0));
+        let refresh = sourcer
+            .set_refreshing(
+                move || {
+                    let runs = Arc::clone(&runs);
+                    async move {
+                        match runs.fetch_add(1, Ordering::SeqCst) {
+                            1 => Err("flaky"),
+                            2 => panic!("intentional panic in a refresh"),
+                            n => Ok(n),
+                        }
+                    }
+                },
+                Duration::from_millis(2),
+            )
+            .unwrap();
+        let producer = tokio::spawn(sourcer.run());
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while refresh.failures() < 2 || sucker.get().await.unwrap_or(0) < 3 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(refresh.failures(), 2);
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refreshing_stops_with_channel() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&runs);
+        sourcer
+            .set_refreshing(
+                move || {
+                    let runs = counted.fetch_add(1, Ordering::SeqCst);
+                    async move { Ok::<_, &str>(runs) }
+                },
+                Duration::from_millis(2),
+            )
+            .unwrap();
+
+        drop(sucker);
+        drop(sourcer);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let stopped = runs.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), stopped);
+    }
+
+    #[tokio::test]
+    async fn test_latest_from_stops_with_channel() {
+        let (sucker, sourcer) = TokioSuck::<u32>::pair();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        sourcer.set_latest_from(rx).unwrap();
+
+        drop(sucker);
+        drop(sourcer);
+        // The next item finds the channel gone, so the receiver is dropped
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while tx.send(1).is_ok() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_set_refreshing_without_runtime() {
+        let (_sucker, sourcer) = TokioSuck::<u32>::pair();
+        let refreshing =
+            sourcer.set_refreshing(|| async { Ok::<_, &str>(1) }, Duration::from_millis(10));
+        assert_eq!(refreshing.err(), Some(Error::NoRuntime));
+    }
 }
@@ -1,3 +1,6 @@
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
 use async_trait::async_trait;
 
 pub use crate::error::Error as ChannelError;
@@ -5,15 +8,54 @@ pub use crate::error::Error as ChannelError;
 #[async_trait]
 pub trait AsyncChannelSender<T>: Send + Sync {
     async fn send(&self, msg: T) -> Result<(), ChannelError>;
+
+    /// Like [send](Self::send), but fails with [ChannelError::Busy] instead of
+    /// waiting when a bounded channel is full
+    ///
+    /// Defaults to trying [send](Self::send) once, for backends whose send
+    /// is cancel safe.
+    fn try_send(&self, msg: T) -> Result<(), ChannelError> {
+        poll_once(self.send(msg)).unwrap_or(Err(ChannelError::Busy))
+    }
 }
 
 #[async_trait]
 pub trait AsyncChannelReceiver<T>: Send + Sync {
     async fn recv(&self) -> Result<T, ChannelError>;
+
+    /// Like [recv](Self::recv), but fails with [ChannelError::Timeout] once
+    /// `timeout` has elapsed
+    async fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError>;
+
+    /// Like [recv](Self::recv), but returns `None` instead of waiting when
+    /// the channel is empty
+    ///
+    /// Defaults to trying [recv](Self::recv) once, for backends whose recv
+    /// is cancel safe.
+    fn try_recv(&self) -> Result<Option<T>, ChannelError> {
+        poll_once(self.recv()).map_or(Ok(None), |received| received.map(Some))
+    }
+}
+
+/// Poll `future` once, without waiting for it to make progress
+fn poll_once<F: Future>(future: F) -> Option<F::Output> {
+    let mut cx = Context::from_waker(Waker::noop());
+    match std::pin::pin!(future).poll(&mut cx) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// A push channel whose latest item is served by
+/// [AsyncSourcer::set_latest_from](crate::AsyncSourcer::set_latest_from)
+#[async_trait]
+pub trait AsyncPushReceiver<T>: Send {
+    /// The next item, or `None` once every sender is gone
+    async fn recv_next(&mut self) -> Option<T>;
 }
 
 pub trait AsyncChannelType {
-    type Sender<T: Send + 'static>: AsyncChannelSender<T>;
+    type Sender<T: Send + 'static>: AsyncChannelSender<T> + 'static;
     type Receiver<T: Send + 'static>: AsyncChannelReceiver<T>;
 
     fn create_request_channel() -> (
@@ -24,4 +66,13 @@ pub trait AsyncChannelType {
         Self::Sender<crate::types::Response<T>>,
         Self::Receiver<crate::types::Response<T>>,
     );
+
+    /// A request channel holding at most `capacity` requests, which is at
+    /// least 1
+    fn create_bounded_request_channel(
+        capacity: usize,
+    ) -> (
+        Self::Sender<crate::types::Request>,
+        Self::Receiver<crate::types::Request>,
+    );
 }
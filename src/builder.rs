@@ -0,0 +1,384 @@
+use std::time::Duration;
+
+#[cfg(feature = "async-tokio")]
+use crate::asynchronous::channel::{AsyncSourcerFor, AsyncSuckerFor};
+#[cfg(feature = "async-tokio")]
+use crate::asynchronous::tokio::TokioChannel;
+#[cfg(feature = "sync")]
+use crate::sync::dynamic::Backend;
+#[cfg(any(
+    feature = "sync-std",
+    feature = "sync-flume",
+    feature = "sync-crossbeam"
+))]
+use crate::sync::dynamic::{DynSourcer, DynSucker};
+use crate::types::{
+    Deadline, FailurePolicy, History, InFlight, LimitPolicy, Limiter, OverflowPolicy, PoisonPolicy,
+    RateLimit, Recovery, ValueSource,
+};
+
+/// The producer thread started by [SuckBuilder::spawn]
+#[cfg(any(
+    feature = "sync-std",
+    feature = "sync-flume",
+    feature = "sync-crossbeam"
+))]
+pub type ProducerHandle = std::thread::JoinHandle<Result<(), crate::Error>>;
+
+/// Configures a channel pair before creating it
+///
+/// ```
+/// use std::time::Duration;
+/// use suck::{Backend, SuckBuilder};
+///
+/// let (sucker, producer) = SuckBuilder::<i32>::new()
+///     .backend(Backend::Crossbeam)
+///     .default_timeout(Duration::from_secs(1))
+///     .thread_name("answer")
+///     .initial_static(42)
+///     .spawn()?;
+///
+/// assert_eq!(sucker.get(), Ok(42));
+///
+/// sucker.close()?;
+/// producer.join().unwrap()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct SuckBuilder<T> {
+    #[cfg(feature = "sync")]
+    backend: Option<Backend>,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+    timeout: Option<Duration>,
+    thread_name: Option<String>,
+    share: Option<fn(&T) -> T>,
+    recovery: Recovery<T>,
+    history: History<T>,
+    limiter: Limiter<T>,
+    deadline: Deadline<T>,
+    poison: PoisonPolicy,
+    initial: ValueSource<T>,
+}
+
+impl<T> Default for SuckBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SuckBuilder<T> {
+    /// Start from the defaults: the default [Backend], no timeout and no source
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "sync")]
+            backend: None,
+            capacity: None,
+            overflow: OverflowPolicy::Block,
+            timeout: None,
+            thread_name: None,
+            share: None,
+            recovery: Recovery::default(),
+            history: History::default(),
+            limiter: Limiter::default(),
+            deadline: Deadline::default(),
+            poison: PoisonPolicy::Recover,
+            initial: ValueSource::None,
+        }
+    }
+
+    /// Use the given channel backend, ignored by [build_async](Self::build_async)
+    #[cfg(feature = "sync")]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Hold at most `capacity` outstanding requests, instead of an unbounded
+    /// queue; a `capacity` of 0 is taken as 1
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Choose what `get` does when the bounded request queue is full
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Give up on `get` after `timeout`, with [Error::Timeout](crate::Error::Timeout)
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Name the producer thread started by [spawn](Self::spawn)
+    pub fn thread_name(mut self, name: impl Into<String>) -> Self {
+        self.thread_name = Some(name.into());
+        self
+    }
+
+    /// Share one evaluation between all requests waiting on it, see
+    /// [Sourcer::with_coalescing](crate::Sourcer::with_coalescing)
+    pub fn coalescing(mut self) -> Self
+    where
+        T: Clone,
+    {
+        self.share = Some(T::clone);
+        self
+    }
+
+    /// Choose what happens when the source panics or returns an error
+    pub fn failure_policy(mut self, policy: FailurePolicy) -> Self
+    where
+        T: Clone,
+    {
+        self.recovery = Recovery::new(policy, T::clone);
+        self
+    }
+
+    /// Keep the last `capacity` values served, see
+    /// [Sourcer::with_history](crate::Sourcer::with_history)
+    pub fn history(mut self, capacity: usize) -> Self
+    where
+        T: Clone,
+    {
+        self.history = History::new(capacity, T::clone);
+        self
+    }
+
+    /// Evaluate closure sources no more often than `limit`, see
+    /// [Sourcer::with_rate_limit](crate::Sourcer::with_rate_limit)
+    pub fn rate_limit(mut self, limit: RateLimit, policy: LimitPolicy) -> Self
+    where
+        T: Clone,
+    {
+        self.limiter = Limiter::new(limit, policy, T::clone);
+        self
+    }
+
+    /// Give up on evaluations taking longer than `timeout`, see
+    /// [Sourcer::with_evaluation_timeout](crate::Sourcer::with_evaluation_timeout)
+    pub fn evaluation_timeout(mut self, timeout: Duration, in_flight: InFlight) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.deadline = Deadline::new(timeout, in_flight);
+        self
+    }
+
+    /// Choose what happens when an [FnMut] source was poisoned by a panic
+    pub fn poison_policy(mut self, poison: PoisonPolicy) -> Self {
+        self.poison = poison;
+        self
+    }
+
+    /// Start with a fixed value, as if by `set_static`
+    pub fn initial_static(mut self, val: T) -> Self
+    where
+        T: Clone,
+    {
+        self.initial = ValueSource::Static {
+            val,
+            clone: T::clone,
+        };
+        self
+    }
+
+    /// Start with a fixed value handed out without copying it, as if by
+    /// `set_shared`
+    pub fn initial_shared(mut self, val: std::sync::Arc<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.initial = ValueSource::Shared(val);
+        self
+    }
+
+    /// Start with a closure that implements [Fn], as if by `set`
+    pub fn initial<F>(mut self, closure: F) -> Self
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        self.initial = ValueSource::dynamic(move || Ok(closure()));
+        self
+    }
+
+    /// Start with a closure that implements [FnMut], as if by `set_mut`
+    pub fn initial_mut<F>(mut self, mut closure: F) -> Self
+    where
+        F: FnMut() -> T + Send + Sync + 'static,
+    {
+        self.initial = ValueSource::dynamic_mut(move || Ok(closure()));
+        self
+    }
+}
+
+#[cfg(any(
+    feature = "sync-std",
+    feature = "sync-flume",
+    feature = "sync-crossbeam"
+))]
+impl<T> SuckBuilder<T>
+where
+    T: Send + 'static,
+{
+    /// Create the configured pair
+    pub fn build(self) -> (DynSucker<T>, DynSourcer<T>) {
+        let backend = self.backend.unwrap_or_default();
+        let (sucker, sourcer) = match self.capacity {
+            Some(capacity) => crate::sync::dynamic::pair_with_bounded(backend, capacity),
+            None => crate::sync::dynamic::pair_with(backend),
+        };
+
+        let sucker = sucker.with_overflow(self.overflow);
+        let sucker = match self.timeout {
+            Some(timeout) => sucker.with_default_timeout(timeout),
+            None => sucker,
+        };
+        let sourcer = sourcer
+            .with_share(self.share)
+            .with_recovery(self.recovery)
+            .with_kept_history(self.history)
+            .with_limiter(self.limiter)
+            .with_deadline(self.deadline)
+            .with_poison_policy(self.poison);
+        sourcer.handle().install(self.initial);
+
+        (sucker, sourcer)
+    }
+
+    /// Create the configured pair and run the producer on its own thread
+    pub fn spawn(mut self) -> std::io::Result<(DynSucker<T>, ProducerHandle)>
+    where
+        T: Sync,
+    {
+        let mut thread = std::thread::Builder::new();
+        if let Some(name) = self.thread_name.take() {
+            thread = thread.name(name);
+        }
+
+        let (sucker, sourcer) = self.build();
+        let handle = thread.spawn(move || sourcer.run())?;
+
+        Ok((sucker, handle))
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl<T> SuckBuilder<T>
+where
+    T: Send + 'static,
+{
+    /// Create the configured pair over the Tokio backend
+    pub fn build_async(
+        self,
+    ) -> (
+        AsyncSuckerFor<TokioChannel, T>,
+        AsyncSourcerFor<TokioChannel, T>,
+    ) {
+        let (sucker, sourcer) = match self.capacity {
+            Some(capacity) => {
+                crate::asynchronous::channel::async_pair_bounded::<TokioChannel, T>(capacity)
+            }
+            None => crate::asynchronous::channel::async_pair::<TokioChannel, T>(),
+        };
+
+        let sucker = sucker.with_overflow(self.overflow);
+        let sucker = match self.timeout {
+            Some(timeout) => sucker.with_default_timeout(timeout),
+            None => sucker,
+        };
+        let sourcer = sourcer
+            .with_share(self.share)
+            .with_recovery(self.recovery)
+            .with_kept_history(self.history)
+            .with_limiter(self.limiter)
+            .with_deadline(self.deadline)
+            .with_poison_policy(self.poison);
+        sourcer.handle().install(self.initial);
+
+        (sucker, sourcer)
+    }
+}
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::thread;
+
+    #[test]
+    fn test_build_installs_initial_source() {
+        let (sucker, sourcer) = SuckBuilder::new()
+            .backend(Backend::Std)
+            .initial_static(42)
+            .build();
+
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(sucker.get(), Ok(42));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_spawn_names_producer_thread() {
+        let (sucker, producer_handle) = SuckBuilder::new()
+            .backend(Backend::Flume)
+            .thread_name("suck-producer")
+            .initial(|| thread::current().name().map(str::to_owned))
+            .spawn()
+            .unwrap();
+
+        assert_eq!(sucker.get(), Ok(Some("suck-producer".to_owned())));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_default_timeout() {
+        let (sucker, producer_handle) = SuckBuilder::new()
+            .backend(Backend::Crossbeam)
+            .default_timeout(Duration::from_millis(20))
+            .initial_mut({
+                let mut count = 0;
+                move || {
+                    count += 1;
+                    if count == 1 {
+                        thread::sleep(Duration::from_millis(200));
+                    }
+                    count
+                }
+            })
+            .spawn()
+            .unwrap();
+
+        assert_eq!(sucker.get(), Err(Error::Timeout));
+        // The late response to the first pull is discarded
+        assert_eq!(sucker.get_timeout(Duration::from_secs(5)), Ok(2));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_build_async() {
+        let (sucker, sourcer) = SuckBuilder::new()
+            .default_timeout(Duration::from_millis(20))
+            .initial_static(42)
+            .build_async();
+
+        // Nothing answers until the producer runs
+        assert_eq!(sucker.get().await, Err(Error::Timeout));
+
+        let producer = tokio::spawn(sourcer.run());
+
+        assert_eq!(sucker.get().await, Ok(42));
+
+        sucker.close().await.unwrap();
+        producer.await.unwrap().unwrap();
+    }
+}
@@ -15,7 +15,57 @@ pub enum Error {
     #[error("Producer has not set a source value")]
     NoSource,
 
-    /// Internal error (e.g., mutex poisoning or source execution failure)
+    /// No response arrived within the timeout
+    #[error("Timed out waiting for the producer")]
+    Timeout,
+
+    /// The bounded request queue is full
+    #[error("Request queue is full")]
+    Busy,
+
+    /// The source returned an error
+    #[error("Source failed: {0}")]
+    SourceFailed(String),
+
+    /// Converting a pulled value failed
+    #[error("Mapping the value failed: {0}")]
+    MapFailed(String),
+
+    /// The lock around an [FnMut] source was poisoned by a panic
+    #[error("Source was poisoned by an earlier panic")]
+    SourcePoisoned,
+
+    /// A source set by `set_shared` has no value of its own to hand out, and
+    /// can only be pulled with `get_arc` or borrowed with `with`
+    #[error("Source is shared and can only be pulled with get_arc or with")]
+    SharedOnly,
+
+    /// `update` only applies to a source set by `set_static`
+    #[error("Source is not a static value")]
+    NotStatic,
+
+    /// The source changed since the generation passed to `compare_and_set`
+    #[error("Source was changed by someone else")]
+    Conflict,
+
+    /// The source may not be evaluated again yet, see
+    /// [RateLimit](crate::RateLimit)
+    #[error("Source is rate limited")]
+    RateLimited,
+
+    /// The source took longer than the evaluation timeout
+    #[error("Source took too long to produce a value")]
+    SourceTimeout,
+
+    /// Nobody waited for the value anymore, so its evaluation was cancelled
+    #[error("Evaluation cancelled as nobody waited for it")]
+    Cancelled,
+
+    /// A Tokio runtime is needed, but none is running on this thread
+    #[error("No Tokio runtime is running")]
+    NoRuntime,
+
+    /// Internal error (e.g., source execution failure)
     #[error("Internal error occurred")] // TODO: Expand on this
     InternalError,
 }
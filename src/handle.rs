@@ -0,0 +1,644 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use arc_swap::{ArcSwap, Guard};
+
+#[cfg(feature = "async-tokio")]
+use crate::asynchronous::traits::AsyncPushReceiver;
+use crate::error::Error;
+use crate::types::{
+    CancellationToken, ChannelState, Doorbell, Generation, Refresh, Slot, ValueSource, Waiters,
+};
+
+/// Changes the source of a running producer
+///
+/// Obtained from `Sourcer::handle` before handing the sourcer to `run`, and
+/// cheap to clone.
+///
+/// ```
+/// use suck::sync::StdSuck;
+///
+/// let (sucker, sourcer) = StdSuck::<i32>::pair();
+/// let handle = sourcer.handle();
+/// let producer = std::thread::spawn(move || sourcer.run());
+///
+/// handle.set_static(42)?;
+/// assert_eq!(sucker.get(), Ok(42));
+///
+/// sucker.close()?;
+/// producer.join().unwrap()?;
+/// # Ok::<(), suck::Error>(())
+/// ```
+pub struct SourceHandle<T> {
+    state: Arc<ChannelState<T>>,
+    waiters: Arc<Waiters>,
+    doorbell: Arc<Doorbell>,
+}
+
+/// A [SourceHandle] that doesn't keep the channel alive, for helpers that
+/// should stop once nobody can pull from it anymore
+pub(crate) struct WeakSourceHandle<T> {
+    state: Weak<ChannelState<T>>,
+    waiters: Weak<Waiters>,
+    doorbell: Weak<Doorbell>,
+}
+
+impl<T> WeakSourceHandle<T> {
+    /// The handle, unless every other one was dropped
+    pub(crate) fn upgrade(&self) -> Option<SourceHandle<T>> {
+        Some(SourceHandle {
+            state: self.state.upgrade()?,
+            waiters: self.waiters.upgrade()?,
+            doorbell: self.doorbell.upgrade()?,
+        })
+    }
+}
+
+impl<T> Clone for SourceHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+            waiters: Arc::clone(&self.waiters),
+            doorbell: Arc::clone(&self.doorbell),
+        }
+    }
+}
+
+impl<T> SourceHandle<T> {
+    pub(crate) fn new(source: ValueSource<T>) -> Self {
+        let generation = match source {
+            ValueSource::None => Generation::default(),
+            _ => Generation::default().next(),
+        };
+        Self {
+            state: Arc::new(ArcSwap::from_pointee(Slot { source, generation })),
+            waiters: Arc::default(),
+            doorbell: Arc::default(),
+        }
+    }
+
+    pub(crate) fn downgrade(&self) -> WeakSourceHandle<T> {
+        WeakSourceHandle {
+            state: Arc::downgrade(&self.state),
+            waiters: Arc::downgrade(&self.waiters),
+            doorbell: Arc::downgrade(&self.doorbell),
+        }
+    }
+
+    /// The consumers waiting for a response
+    pub(crate) fn waiters(&self) -> &Waiters {
+        &self.waiters
+    }
+
+    /// Wakes the producer for parked requests
+    pub(crate) fn doorbell(&self) -> &Doorbell {
+        &self.doorbell
+    }
+
+    /// The current source along with its generation
+    pub(crate) fn load(&self) -> Guard<Arc<Slot<T>>> {
+        self.state.load()
+    }
+
+    /// Replace the current source, starting a new generation
+    pub(crate) fn install(&self, source: ValueSource<T>) {
+        let mut slot = Arc::new(Slot {
+            source,
+            generation: Generation::default(),
+        });
+        loop {
+            let current = self.state.load();
+            Arc::get_mut(&mut slot)
+                .expect("slot is only shared once swapped in")
+                .generation = current.generation.next();
+            let previous = self.state.compare_and_swap(&current, Arc::clone(&slot));
+            if Arc::ptr_eq(&previous, &current) {
+                self.doorbell.changed();
+                return;
+            }
+        }
+    }
+
+    /// Close the channel after `current` ran out, unless the source was
+    /// changed since
+    pub(crate) fn exhausted(&self, current: &Arc<Slot<T>>) {
+        if matches!(current.source, ValueSource::Cleared) {
+            return;
+        }
+        let slot = Slot {
+            source: ValueSource::Cleared,
+            generation: current.generation.next(),
+        };
+        self.state.compare_and_swap(current, Arc::new(slot));
+    }
+
+    /// Keep the first value of a lazy source for good, unless the source was
+    /// changed since `current` was loaded
+    pub(crate) fn settle(&self, current: &Arc<Slot<T>>, value: &T) {
+        let ValueSource::Lazy { clone, .. } = current.source else {
+            return;
+        };
+        let slot = Slot {
+            source: ValueSource::Static {
+                val: clone(value),
+                clone,
+            },
+            // Still the same value
+            generation: current.generation,
+        };
+        self.state.compare_and_swap(current, Arc::new(slot));
+    }
+
+    /// The generation of the current source
+    ///
+    /// Starts at `Generation::default()` before the first source is set, and
+    /// moves on with every change.
+    pub fn generation(&self) -> Generation {
+        self.state.load().generation
+    }
+
+    /// Whether the channel was closed
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(self.state.load().source, ValueSource::Cleared)
+    }
+
+    /// Run `f` against the current value where it is kept, without the
+    /// producer
+    ///
+    /// Hands `f` back if the source has to be evaluated by the producer.
+    pub(crate) fn with_fixed<F, R>(&self, f: F) -> Result<Result<R, Error>, F>
+    where
+        T: 'static,
+        F: FnOnce(&T) -> R,
+    {
+        let state = self.state.load();
+        match &state.source {
+            ValueSource::Static { val, .. } => Ok(Ok(f(val))),
+            ValueSource::Shared(val) => Ok(val.downcast_ref().map(f).ok_or(Error::InternalError)),
+            ValueSource::None => Ok(Err(Error::NoSource)),
+            ValueSource::Cleared => Ok(Err(Error::ChannelClosed)),
+            // Anything else is evaluated by the producer
+            _ => Err(f),
+        }
+    }
+}
+
+impl<T> Default for SourceHandle<T> {
+    fn default() -> Self {
+        Self::new(ValueSource::None)
+    }
+}
+
+impl<T> SourceHandle<T>
+where
+    T: Send + 'static,
+{
+    /// Set a fixed value
+    pub fn set_static(&self, val: T) -> Result<(), Error>
+    where
+        T: Clone,
+    {
+        self.install(ValueSource::Static {
+            val,
+            clone: T::clone,
+        });
+        Ok(())
+    }
+
+    /// Set a fixed value that is handed out without copying it
+    ///
+    /// Pull it with `get_arc`, or borrow it with `with`. As `T` needn't be
+    /// [Clone], pulls that need a value of their own, such as `get` and the
+    /// combinators built on it, fail with [Error::SharedOnly].
+    pub fn set_shared(&self, val: Arc<T>) -> Result<(), Error>
+    where
+        T: Sync,
+    {
+        self.install(ValueSource::Shared(val));
+        Ok(())
+    }
+
+    /// Set a closure that implements [Fn]
+    pub fn set<F>(&self, closure: F) -> Result<(), Error>
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        self.install(ValueSource::dynamic(move || Ok(closure())));
+        Ok(())
+    }
+
+    /// Set a closure that implements [FnMut]
+    pub fn set_mut<F>(&self, mut closure: F) -> Result<(), Error>
+    where
+        F: FnMut() -> T + Send + Sync + 'static,
+    {
+        self.install(ValueSource::dynamic_mut(move || Ok(closure())));
+        Ok(())
+    }
+
+    /// Set a fallible closure that implements [Fn]
+    ///
+    /// Errors are reported as [Error::SourceFailed], subject to the
+    /// [FailurePolicy](crate::FailurePolicy).
+    pub fn set_fallible<F, E>(&self, closure: F) -> Result<(), Error>
+    where
+        F: Fn() -> Result<T, E> + Send + Sync + 'static,
+        E: std::fmt::Display,
+    {
+        self.install(ValueSource::dynamic(move || {
+            closure().map_err(|e| Error::SourceFailed(e.to_string()))
+        }));
+        Ok(())
+    }
+
+    /// Set a fallible closure that implements [FnMut]
+    ///
+    /// Errors are reported as [Error::SourceFailed], subject to the
+    /// [FailurePolicy](crate::FailurePolicy).
+    pub fn set_fallible_mut<F, E>(&self, mut closure: F) -> Result<(), Error>
+    where
+        F: FnMut() -> Result<T, E> + Send + 'static,
+        E: std::fmt::Display,
+    {
+        self.install(ValueSource::dynamic_mut(move || {
+            closure().map_err(|e| Error::SourceFailed(e.to_string()))
+        }));
+        Ok(())
+    }
+
+    /// Set a closure that only runs on the first pull, its value then being
+    /// kept as if set by `set_static`
+    ///
+    /// Pulls arriving meanwhile wait for it to finish. Should it panic, the
+    /// next pull runs it again.
+    pub fn set_lazy<F>(&self, closure: F) -> Result<(), Error>
+    where
+        T: Clone,
+        F: FnMut() -> T + Send + 'static,
+    {
+        let mut closure = closure;
+        self.install(ValueSource::Lazy {
+            init: Mutex::new(Box::new(move || Ok(closure()))),
+            clone: T::clone,
+        });
+        Ok(())
+    }
+
+    /// Set a closure that a helper thread runs ahead of pulls, keeping up to
+    /// `depth` values ready
+    ///
+    /// Each pull takes the oldest ready value, waiting for one only if there
+    /// is none. The helper thread stops once the source is changed. A `depth`
+    /// of 0 runs the closure on each pull instead.
+    pub fn set_prefetch<F>(&self, closure: F, depth: usize) -> Result<(), Error>
+    where
+        F: FnMut() -> T + Send + 'static,
+    {
+        if depth == 0 {
+            let mut closure = closure;
+            self.install(ValueSource::dynamic_mut(move || Ok(closure())));
+            return Ok(());
+        }
+        // The helper thread holds one more value while it waits for room
+        let (tx, rx) = std::sync::mpsc::sync_channel(depth - 1);
+        std::thread::spawn(move || {
+            let mut closure = closure;
+            loop {
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(&mut closure));
+                // The source was changed
+                if tx.send(result).is_err() {
+                    break;
+                }
+            }
+        });
+        self.install(ValueSource::dynamic_mut(move || match rx.recv() {
+            Ok(Ok(value)) => Ok(value),
+            // Report the panic as if the closure ran here
+            Ok(Err(panic)) => std::panic::resume_unwind(panic),
+            Err(_) => Err(Error::InternalError),
+        }));
+        Ok(())
+    }
+
+    /// Like [set_prefetch](Self::set_prefetch), keeping the values ready on a
+    /// Tokio task and running the closure on Tokio's blocking threads
+    #[cfg(feature = "async-tokio")]
+    pub(crate) fn prefetch_async<F>(&self, closure: F, depth: usize) -> Result<(), Error>
+    where
+        F: FnMut() -> T + Send + 'static,
+    {
+        if depth == 0 {
+            let mut closure = closure;
+            self.install(ValueSource::dynamic_mut(move || Ok(closure())));
+            return Ok(());
+        }
+        let runtime = tokio::runtime::Handle::try_current().map_err(|_| Error::NoRuntime)?;
+        let (tx, rx) = tokio::sync::mpsc::channel(depth);
+        runtime.spawn(async move {
+            let mut closure = closure;
+            // Make room before running the closure, so no more than `depth`
+            // values are ready; the source was changed once that fails
+            while let Ok(permit) = tx.reserve().await {
+                let run = tokio::task::spawn_blocking(move || {
+                    let result =
+                        std::panic::catch_unwind(std::panic::AssertUnwindSafe(&mut closure));
+                    (closure, result)
+                });
+                let Ok((returned, result)) = run.await else {
+                    break;
+                };
+                closure = returned;
+                permit.send(result);
+            }
+        });
+        self.install(ValueSource::Stream(Mutex::new(Box::pin(Prefetched(rx)))));
+        Ok(())
+    }
+
+    /// Set a closure that implements [FnMut] and may give up, returning
+    /// `None`, once its [CancellationToken] says nobody waits for the value
+    /// anymore
+    pub fn set_cancellable<F>(&self, mut closure: F) -> Result<(), Error>
+    where
+        F: FnMut(&CancellationToken) -> Option<T> + Send + 'static,
+    {
+        let waiters = Arc::clone(&self.waiters);
+        self.install(ValueSource::dynamic_mut(move || {
+            closure(&CancellationToken::new(Arc::clone(&waiters))).ok_or(Error::Cancelled)
+        }));
+        Ok(())
+    }
+
+    /// Hand out the elements of `iter` one per pull, closing the channel once
+    /// it runs out
+    pub fn set_iter<I>(&self, iter: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+    {
+        self.install(ValueSource::iter(iter.into_iter()));
+        Ok(())
+    }
+
+    /// Replace a value set by `set_static` with `f` applied to it
+    ///
+    /// Concurrent changes are never lost: should the source change while `f`
+    /// runs, `f` runs again on the newer value.
+    pub fn update<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&T) -> T,
+    {
+        let mut result = Ok(());
+        self.state.rcu(|current| {
+            result = match &current.source {
+                ValueSource::Static { .. } => Ok(()),
+                ValueSource::None => Err(Error::NoSource),
+                ValueSource::Cleared => Err(Error::ChannelClosed),
+                _ => Err(Error::NotStatic),
+            };
+            match &current.source {
+                ValueSource::Static { val, clone } => Arc::new(Slot {
+                    source: ValueSource::Static {
+                        val: f(val),
+                        clone: *clone,
+                    },
+                    generation: current.generation.next(),
+                }),
+                // Leave any other source as it is
+                _ => Arc::clone(current),
+            }
+        });
+        if result.is_ok() {
+            self.doorbell.changed();
+        }
+        result
+    }
+
+    /// Set a fixed value, unless the source changed since generation
+    /// `expected`
+    ///
+    /// Returns the generation of the new source, or fails with
+    /// [Error::Conflict] if another change came first.
+    pub fn compare_and_set(&self, expected: Generation, val: T) -> Result<Generation, Error>
+    where
+        T: Clone,
+    {
+        let current = self.state.load();
+        if matches!(current.source, ValueSource::Cleared) {
+            return Err(Error::ChannelClosed);
+        }
+        if current.generation != expected {
+            return Err(Error::Conflict);
+        }
+
+        let generation = expected.next();
+        let slot = Slot {
+            source: ValueSource::Static {
+                val,
+                clone: T::clone,
+            },
+            generation,
+        };
+        let previous = self.state.compare_and_swap(&current, Arc::new(slot));
+        if Arc::ptr_eq(&previous, &current) {
+            self.doorbell.changed();
+            Ok(generation)
+        } else {
+            Err(Error::Conflict)
+        }
+    }
+
+    /// Close the channel
+    pub fn close(&self) -> Result<(), Error> {
+        self.install(ValueSource::Cleared);
+        Ok(())
+    }
+}
+
+impl<T> SourceHandle<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    /// Serve the latest item pushed into `receiver`, which is drained on a
+    /// thread of its own
+    ///
+    /// The current source stays until the first item arrives. Draining stops
+    /// once every sender is gone, leaving the last item in place, or at the
+    /// next item after the source was changed by other means or the channel
+    /// was dropped.
+    pub fn set_latest_from<R>(&self, receiver: R) -> Result<(), Error>
+    where
+        R: IntoIterator<Item = T>,
+        R::IntoIter: Send + 'static,
+    {
+        self.follow(self.generation(), receiver.into_iter());
+        Ok(())
+    }
+
+    /// Like [set_latest_from](Self::set_latest_from), but wait for the first
+    /// item and serve it right away
+    ///
+    /// Fails with [Error::ProducerDisconnected] if every sender is gone before
+    /// an item arrives.
+    pub fn set_latest_from_wait<R>(&self, receiver: R) -> Result<(), Error>
+    where
+        R: IntoIterator<Item = T>,
+        R::IntoIter: Send + 'static,
+    {
+        let mut items = receiver.into_iter();
+        let first = items.next().ok_or(Error::ProducerDisconnected)?;
+        self.set_static(first)?;
+        self.follow(self.generation(), items);
+        Ok(())
+    }
+
+    /// Set a fallible closure that a background thread runs every
+    /// `interval`, pulls getting its latest value without waiting
+    ///
+    /// The current source stays until the first run succeeds. A run that
+    /// fails or panics keeps the value before it, and is counted by the
+    /// returned [Refresh]. Refreshing stops once the source is changed by
+    /// other means, or the channel is dropped.
+    pub fn set_refreshing<F, E>(&self, closure: F, interval: Duration) -> Result<Refresh, Error>
+    where
+        F: FnMut() -> Result<T, E> + Send + 'static,
+    {
+        let refresh = Refresh::default();
+        let weak = self.downgrade();
+        let failures = refresh.clone();
+        let mut generation = self.generation();
+        std::thread::spawn(move || {
+            let mut closure = closure;
+            loop {
+                let refreshed =
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(&mut closure));
+                // Only hold on to the channel while refreshing it, so it can
+                // be dropped in between
+                let Some(handle) = weak.upgrade() else {
+                    break;
+                };
+                match refreshed {
+                    Ok(Ok(value)) => match handle.compare_and_set(generation, value) {
+                        Ok(next) => generation = next,
+                        Err(_) => break,
+                    },
+                    _ if handle.generation() != generation => break,
+                    _ => failures.failed(),
+                }
+                drop(handle);
+                std::thread::sleep(interval);
+            }
+        });
+        Ok(refresh)
+    }
+
+    /// Like [set_refreshing](Self::set_refreshing), running each refresh as
+    /// a Tokio task
+    #[cfg(feature = "async-tokio")]
+    pub(crate) fn refresh_async<F, Fut, E>(
+        &self,
+        closure: F,
+        interval: Duration,
+    ) -> Result<Refresh, Error>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let runtime = tokio::runtime::Handle::try_current().map_err(|_| Error::NoRuntime)?;
+        let refresh = Refresh::default();
+        let weak = self.downgrade();
+        let failures = refresh.clone();
+        let mut generation = self.generation();
+        runtime.spawn(async move {
+            let mut closure = closure;
+            loop {
+                // A task of its own catches panics
+                let run = closure();
+                let refreshed = tokio::spawn(async move { run.await.ok() }).await;
+                let Some(handle) = weak.upgrade() else {
+                    break;
+                };
+                match refreshed {
+                    Ok(Some(value)) => match handle.compare_and_set(generation, value) {
+                        Ok(next) => generation = next,
+                        Err(_) => break,
+                    },
+                    _ if handle.generation() != generation => break,
+                    _ => failures.failed(),
+                }
+                drop(handle);
+                tokio::time::sleep(interval).await;
+            }
+        });
+        Ok(refresh)
+    }
+
+    /// Set each item as a fixed value for as long as the source is still the
+    /// one of `generation` or one set here
+    fn follow<I>(&self, generation: Generation, items: I)
+    where
+        I: Iterator<Item = T> + Send + 'static,
+    {
+        let weak = self.downgrade();
+        std::thread::spawn(move || {
+            let mut generation = generation;
+            for item in items {
+                // Only hold on to the channel while setting an item, so it
+                // can be dropped in between
+                let Some(handle) = weak.upgrade() else {
+                    break;
+                };
+                match handle.compare_and_set(generation, item) {
+                    Ok(next) => generation = next,
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+
+    /// Like [follow](Self::follow), on a Tokio task
+    #[cfg(feature = "async-tokio")]
+    pub(crate) fn follow_async<R>(&self, generation: Generation, receiver: R) -> Result<(), Error>
+    where
+        R: AsyncPushReceiver<T> + 'static,
+    {
+        let runtime = tokio::runtime::Handle::try_current().map_err(|_| Error::NoRuntime)?;
+        let weak = self.downgrade();
+        runtime.spawn(async move {
+            let mut receiver = receiver;
+            let mut generation = generation;
+            while let Some(item) = receiver.recv_next().await {
+                let Some(handle) = weak.upgrade() else {
+                    break;
+                };
+                match handle.compare_and_set(generation, item) {
+                    Ok(next) => generation = next,
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// The values a prefetch task keeps ready, oldest first
+#[cfg(feature = "async-tokio")]
+struct Prefetched<T>(tokio::sync::mpsc::Receiver<std::thread::Result<T>>);
+
+#[cfg(feature = "async-tokio")]
+impl<T> futures_core::Stream for Prefetched<T> {
+    type Item = T;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<T>> {
+        self.0.poll_recv(cx).map(|ready| {
+            ready.map(|result| match result {
+                Ok(value) => value,
+                // Report the panic as if the closure ran here
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+        })
+    }
+}
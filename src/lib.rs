@@ -2,16 +2,39 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
 pub mod error;
+#[cfg(any(feature = "sync", feature = "async"))]
+pub mod handle;
 
 #[cfg(feature = "async")]
 pub mod asynchronous;
+#[cfg(any(feature = "sync", feature = "async"))]
+pub mod builder;
 #[cfg(feature = "sync")]
 pub mod sync;
 #[cfg(any(feature = "sync", feature = "async"))]
+pub mod testing;
+#[cfg(any(feature = "sync", feature = "async"))]
 pub mod types;
 
 #[cfg(feature = "async")]
-pub use asynchronous::channel::{AsyncSourcer, AsyncSucker};
+pub use asynchronous::channel::{AsyncSourcer, AsyncSucker, async_pair, async_pair_bounded};
+#[cfg(feature = "async")]
+pub use asynchronous::pull::{
+    AsyncFallbackSucker, AsyncMappedSucker, AsyncPull, AsyncZip, async_zip,
+};
+#[cfg(any(feature = "sync", feature = "async"))]
+pub use builder::SuckBuilder;
 pub use error::Error;
+#[cfg(any(feature = "sync", feature = "async"))]
+pub use handle::SourceHandle;
+#[cfg(feature = "sync")]
+pub use sync::channel::{Sourcer, Sucker, pair, pair_bounded};
 #[cfg(feature = "sync")]
-pub use sync::channel::{Sourcer, Sucker};
+pub use sync::dynamic::{Backend, DynSourcer, DynSucker, pair_with, pair_with_bounded};
+#[cfg(feature = "sync")]
+pub use sync::pull::{FallbackSucker, MappedSucker, Pull, Zip, zip};
+#[cfg(any(feature = "sync", feature = "async"))]
+pub use types::{
+    CancellationToken, FailurePolicy, Generation, InFlight, LimitPolicy, OverflowPolicy,
+    PoisonPolicy, RateLimit, Refresh, Served,
+};
@@ -1,9 +1,84 @@
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::error::Error;
-use crate::sync::traits::{ChannelReceiver, ChannelSender};
-use crate::types::{ChannelState, Request, Response, ValueSource};
+use crate::handle::SourceHandle;
+use crate::sync::traits::{ChannelReceiver, ChannelSender, ChannelType};
+use crate::types::{
+    CancellationToken, Coalesced, Deadline, Evaluation, FailurePolicy, Generation, History,
+    InFlight, LimitPolicy, Limiter, Mailbox, OverflowPolicy, Parked, ParkedRequest, PoisonPolicy,
+    RateLimit, Recovery, Refresh, Reply, Request, RequestId, Response, Served, deadline_after,
+};
+
+/// The [Sucker] produced by a [ChannelType]
+pub type SuckerFor<C, T> =
+    Sucker<T, <C as ChannelType>::Sender<Request>, <C as ChannelType>::Receiver<Response<T>>>;
+
+/// The [Sourcer] produced by a [ChannelType]
+pub type SourcerFor<C, T> =
+    Sourcer<T, <C as ChannelType>::Receiver<Request>, <C as ChannelType>::Sender<Response<T>>>;
+
+/// Create a [Sucker]/[Sourcer] pair over any [ChannelType]
+///
+/// This is how third-party backends are plugged in; the bundled backends
+/// (e.g. [StdSuck](crate::sync::StdSuck)) are thin wrappers around it. The
+/// request sender must be [Send] and [Sync], as changing the source wakes
+/// the producer from whichever thread changed it.
+pub fn pair<C, T>() -> (SuckerFor<C, T>, SourcerFor<C, T>)
+where
+    C: ChannelType,
+    C::Sender<Request>: Send + Sync + 'static,
+{
+    let (request_tx, request_rx) = C::create_request_channel();
+    let (response_tx, response_rx) = C::create_response_channel::<T>();
+
+    connect(request_tx, request_rx, response_tx, response_rx)
+}
+
+/// Create a [Sucker]/[Sourcer] pair over any [ChannelType], holding at most
+/// `capacity` outstanding requests
+///
+/// A `capacity` of 0 is taken as 1. What happens when the queue is full is
+/// chosen by [Sucker::with_overflow].
+pub fn pair_bounded<C, T>(capacity: usize) -> (SuckerFor<C, T>, SourcerFor<C, T>)
+where
+    C: ChannelType,
+    C::Sender<Request>: Send + Sync + 'static,
+{
+    // Responses never outnumber outstanding requests, so only those are bounded
+    let (request_tx, request_rx) = C::create_bounded_request_channel(capacity.max(1));
+    let (response_tx, response_rx) = C::create_response_channel::<T>();
+
+    connect(request_tx, request_rx, response_tx, response_rx)
+}
+
+/// Join both halves of the request and response channels into a pair
+pub(crate) fn connect<T, RT, RR, ST, SR>(
+    request_tx: RT,
+    request_rx: RR,
+    response_tx: ST,
+    response_rx: SR,
+) -> (Sucker<T, RT, SR>, Sourcer<T, RR, ST>)
+where
+    RT: ChannelSender<Request> + Send + Sync + 'static,
+    RR: ChannelReceiver<Request>,
+    ST: ChannelSender<Response<T>>,
+    SR: ChannelReceiver<Response<T>>,
+{
+    let source = SourceHandle::default();
+    let coalesced = Arc::default();
+
+    let sourcer = Sourcer::new(
+        request_rx,
+        response_tx,
+        source.clone(),
+        Arc::clone(&coalesced),
+    );
+    let sucker = Sucker::new(request_tx, response_rx, coalesced, source);
+
+    (sucker, sourcer)
+}
 
 /// The consumer side of the channel that requests values
 pub struct Sucker<T, ST, SR>
@@ -11,10 +86,14 @@ where
     ST: ChannelSender<Request>,
     SR: ChannelReceiver<Response<T>>,
 {
-    request_tx: ST,
+    request_tx: Arc<ST>,
     response_rx: SR,
     closed: AtomicBool,
-    _phantom: std::marker::PhantomData<T>,
+    timeout: Option<Duration>,
+    overflow: OverflowPolicy,
+    mailbox: Mailbox<T>,
+    coalesced: Arc<Coalesced>,
+    source: SourceHandle<T>,
 }
 
 impl<T, ST, SR> Sucker<T, ST, SR>
@@ -23,14 +102,47 @@ where
     SR: ChannelReceiver<Response<T>>,
 {
     /// Create a new Sucker instance
-    pub(crate) fn new(request_tx: ST, response_rx: SR) -> Self {
+    pub(crate) fn new(
+        request_tx: ST,
+        response_rx: SR,
+        coalesced: Arc<Coalesced>,
+        source: SourceHandle<T>,
+    ) -> Self
+    where
+        ST: Send + Sync + 'static,
+    {
+        let request_tx = Arc::new(request_tx);
+        // Waking the producer must not keep the queue open once the sucker
+        // is gone
+        let sender = Arc::downgrade(&request_tx);
+        source.doorbell().connect(move || {
+            if let Some(request_tx) = sender.upgrade() {
+                let _ = request_tx.try_send(Request::Wake);
+            }
+        });
         Self {
             request_tx,
             response_rx,
             closed: AtomicBool::new(false),
-            _phantom: std::marker::PhantomData,
+            timeout: None,
+            overflow: OverflowPolicy::default(),
+            mailbox: Mailbox::default(),
+            coalesced,
+            source,
         }
     }
+
+    /// Give up on [get](Self::get) after `timeout`, with [Error::Timeout]
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Choose what [get](Self::get) does when the request queue is full
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
 }
 
 /// The producer side of the channel that provides values
@@ -41,7 +153,14 @@ where
 {
     request_rx: SR,
     response_tx: ST,
-    state: ChannelState<T>,
+    source: SourceHandle<T>,
+    coalesced: Arc<Coalesced>,
+    share: Option<fn(&T) -> T>,
+    recovery: Recovery<T>,
+    history: History<T>,
+    limiter: Limiter<T>,
+    deadline: Deadline<T>,
+    poison: PoisonPolicy,
     _phantom: std::marker::PhantomData<T>,
 }
 
@@ -51,14 +170,111 @@ where
     ST: ChannelSender<Response<T>>,
 {
     /// Create a new Sourcer instance
-    pub(crate) fn new(request_rx: SR, response_tx: ST, state: ChannelState<T>) -> Self {
+    pub(crate) fn new(
+        request_rx: SR,
+        response_tx: ST,
+        source: SourceHandle<T>,
+        coalesced: Arc<Coalesced>,
+    ) -> Self {
         Self {
             request_rx,
             response_tx,
-            state,
+            source,
+            coalesced,
+            share: None,
+            recovery: Recovery::default(),
+            history: History::default(),
+            limiter: Limiter::default(),
+            deadline: Deadline::default(),
+            poison: PoisonPolicy::default(),
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Answer every request waiting on an evaluation with a clone of its
+    /// result, instead of evaluating the source once per request
+    ///
+    /// This covers requests already queued when the evaluation starts, those
+    /// that arrive while it runs, and those that found a full queue under
+    /// [OverflowPolicy::Coalesce].
+    pub fn with_coalescing(self) -> Self
+    where
+        T: Clone,
+    {
+        self.with_share(Some(T::clone))
+    }
+
+    pub(crate) fn with_share(mut self, share: Option<fn(&T) -> T>) -> Self {
+        self.share = share;
+        self.coalesced.set_shared(share.is_some());
+        self
+    }
+
+    /// Choose what happens when the source panics or returns an error
+    pub fn with_failure_policy(self, policy: FailurePolicy) -> Self
+    where
+        T: Clone,
+    {
+        self.with_recovery(Recovery::new(policy, T::clone))
+    }
+
+    pub(crate) fn with_recovery(mut self, recovery: Recovery<T>) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Keep the last `capacity` values served, with their generations and
+    /// when they were produced, for consumers to look at with `history`
+    pub fn with_history(self, capacity: usize) -> Self
+    where
+        T: Clone,
+    {
+        self.with_kept_history(History::new(capacity, T::clone))
+    }
+
+    pub(crate) fn with_kept_history(mut self, history: History<T>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Evaluate closure sources no more often than `limit`, answering pulls
+    /// over it according to `policy`
+    pub fn with_rate_limit(self, limit: RateLimit, policy: LimitPolicy) -> Self
+    where
+        T: Clone,
+    {
+        self.with_limiter(Limiter::new(limit, policy, T::clone))
+    }
+
+    pub(crate) fn with_limiter(mut self, limiter: Limiter<T>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Give up on evaluations that take longer than `timeout`, failing with
+    /// [Error::SourceTimeout]
+    ///
+    /// Closure sources run on a helper thread, kept for the next evaluation
+    /// and left behind should they hang. `in_flight` chooses whether later
+    /// pulls wait for such an evaluation or start another.
+    pub fn with_evaluation_timeout(self, timeout: Duration, in_flight: InFlight) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.with_deadline(Deadline::new(timeout, in_flight))
+    }
+
+    pub(crate) fn with_deadline(mut self, deadline: Deadline<T>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Choose what happens when an [FnMut] source was poisoned by a panic,
+    /// see [PoisonPolicy]
+    pub fn with_poison_policy(mut self, poison: PoisonPolicy) -> Self {
+        self.poison = poison;
+        self
+    }
 }
 
 impl<T, SR, ST> Sourcer<T, SR, ST>
@@ -67,16 +283,32 @@ where
     SR: ChannelReceiver<Request>,
     ST: ChannelSender<Response<T>>,
 {
+    /// A handle for changing the source while [run](Self::run) is running
+    pub fn handle(&self) -> SourceHandle<T> {
+        self.source.clone()
+    }
+
+    /// The generation of the current source
+    pub fn generation(&self) -> Generation {
+        self.source.generation()
+    }
+
     /// Set a fixed value
     pub fn set_static(&self, val: T) -> Result<(), Error>
     where
         T: Clone,
     {
-        self.state.swap(Arc::new(ValueSource::Static {
-            val,
-            clone: T::clone,
-        }));
-        Ok(())
+        self.source.set_static(val)
+    }
+
+    /// Set a fixed value that is handed out without copying it
+    ///
+    /// See [SourceHandle::set_shared] for the pulls it can answer.
+    pub fn set_shared(&self, val: Arc<T>) -> Result<(), Error>
+    where
+        T: Sync,
+    {
+        self.source.set_shared(val)
     }
 
     /// Set a closure that implements [Fn]
@@ -84,9 +316,7 @@ where
     where
         F: Fn() -> T + Send + Sync + 'static,
     {
-        self.state
-            .swap(Arc::new(ValueSource::Dynamic(Box::new(closure))));
-        Ok(())
+        self.source.set(closure)
     }
 
     /// Set a closure that implements [FnMut]
@@ -94,80 +324,400 @@ where
     where
         F: FnMut() -> T + Send + Sync + 'static,
     {
-        self.state
-            .swap(Arc::new(ValueSource::DynamicMut(Mutex::new(Box::new(
-                closure,
-            )))));
-        Ok(())
+        self.source.set_mut(closure)
+    }
+
+    /// Set a fallible closure that implements [Fn]
+    ///
+    /// Errors are reported as [Error::SourceFailed], subject to the
+    /// [FailurePolicy].
+    pub fn set_fallible<F, E>(&self, closure: F) -> Result<(), Error>
+    where
+        F: Fn() -> Result<T, E> + Send + Sync + 'static,
+        E: std::fmt::Display,
+    {
+        self.source.set_fallible(closure)
+    }
+
+    /// Set a fallible closure that implements [FnMut]
+    ///
+    /// Errors are reported as [Error::SourceFailed], subject to the
+    /// [FailurePolicy].
+    pub fn set_fallible_mut<F, E>(&self, closure: F) -> Result<(), Error>
+    where
+        F: FnMut() -> Result<T, E> + Send + 'static,
+        E: std::fmt::Display,
+    {
+        self.source.set_fallible_mut(closure)
+    }
+
+    /// Set a closure that only runs on the first pull, its value then being
+    /// kept as if set by `set_static`
+    ///
+    /// Pulls arriving meanwhile wait for it to finish. Should it panic, the
+    /// next pull runs it again.
+    pub fn set_lazy<F>(&self, closure: F) -> Result<(), Error>
+    where
+        T: Clone,
+        F: FnMut() -> T + Send + 'static,
+    {
+        self.source.set_lazy(closure)
+    }
+
+    /// Set a closure that a helper thread runs ahead of pulls, keeping up to
+    /// `depth` values ready
+    ///
+    /// See [SourceHandle::set_prefetch].
+    pub fn set_prefetch<F>(&self, closure: F, depth: usize) -> Result<(), Error>
+    where
+        F: FnMut() -> T + Send + 'static,
+    {
+        self.source.set_prefetch(closure, depth)
+    }
+
+    /// Set a fallible closure that a background thread runs every
+    /// `interval`, pulls getting its latest value without waiting
+    ///
+    /// See [SourceHandle::set_refreshing].
+    pub fn set_refreshing<F, E>(&self, closure: F, interval: Duration) -> Result<Refresh, Error>
+    where
+        T: Clone + Sync,
+        F: FnMut() -> Result<T, E> + Send + 'static,
+    {
+        self.source.set_refreshing(closure, interval)
+    }
+
+    /// Set a closure that implements [FnMut] and may give up, returning
+    /// `None`, once its [CancellationToken] says nobody waits for the value
+    /// anymore
+    pub fn set_cancellable<F>(&self, closure: F) -> Result<(), Error>
+    where
+        F: FnMut(&CancellationToken) -> Option<T> + Send + 'static,
+    {
+        self.source.set_cancellable(closure)
+    }
+
+    /// Hand out the elements of `iter` one per pull, closing the channel once
+    /// it runs out
+    pub fn set_iter<I>(&self, iter: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+    {
+        self.source.set_iter(iter)
+    }
+
+    /// Serve the latest item pushed into `receiver`, such as a flume,
+    /// crossbeam or std `mpsc` receiver, which is drained on a thread of its
+    /// own
+    ///
+    /// See [SourceHandle::set_latest_from].
+    pub fn set_latest_from<R>(&self, receiver: R) -> Result<(), Error>
+    where
+        T: Clone + Sync,
+        R: IntoIterator<Item = T>,
+        R::IntoIter: Send + 'static,
+    {
+        self.source.set_latest_from(receiver)
+    }
+
+    /// Like [set_latest_from](Self::set_latest_from), but wait for the first
+    /// item and serve it right away
+    pub fn set_latest_from_wait<R>(&self, receiver: R) -> Result<(), Error>
+    where
+        T: Clone + Sync,
+        R: IntoIterator<Item = T>,
+        R::IntoIter: Send + 'static,
+    {
+        self.source.set_latest_from_wait(receiver)
+    }
+
+    /// Replace a value set by `set_static` with `f` applied to it
+    ///
+    /// Concurrent changes are never lost: should the source change while `f`
+    /// runs, `f` runs again on the newer value.
+    pub fn update<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(&T) -> T,
+    {
+        self.source.update(f)
+    }
+
+    /// Set a fixed value, unless the source changed since generation
+    /// `expected`
+    ///
+    /// Returns the generation of the new source, or fails with
+    /// [Error::Conflict] if another change came first.
+    pub fn compare_and_set(&self, expected: Generation, val: T) -> Result<Generation, Error>
+    where
+        T: Clone,
+    {
+        self.source.compare_and_set(expected, val)
     }
 
     /// Close the channel
     pub fn close(&self) -> Result<(), Error> {
-        self.state.swap(Arc::new(ValueSource::Cleared));
-        Ok(())
+        self.source.close()
     }
 
     /// Handles requests - blocking
     pub fn run(self) -> Result<(), Error> {
+        let mut parked = Parked::default();
         loop {
-            match self.request_rx.recv() {
-                Ok(Request::GetValue) => {
-                    let response = self.handle_get_value()?;
-                    if self.response_tx.send(response).is_err() {
-                        // Consumer disconnected
-                        break;
-                    }
+            let mut running = match self.request_rx.recv() {
+                Ok(Request::GetValue(id)) => self.answer(vec![id], &mut parked)?,
+                Ok(Request::GetValueWait(id)) => {
+                    parked.push(id, ParkedRequest::Value);
+                    true
+                }
+                Ok(Request::GetNext(id, after)) => {
+                    parked.push(id, ParkedRequest::Next(after));
+                    true
                 }
+                Ok(Request::GetIfChanged(id, last)) => self.answer_if_changed(id, last)?,
+                Ok(Request::GetArc(id)) => self.answer_shared(id)?,
+                Ok(Request::History(id, n)) => self.answer_history(id, n),
+                Ok(Request::Wake) => self.answer(Vec::new(), &mut parked)?,
                 Ok(Request::Close) => {
                     // Close channel
                     self.close()?;
-                    break;
-                }
-                Err(_) => {
-                    // Consumer disconnected
-                    break;
+                    self.unpark(&mut parked)?;
+                    false
                 }
+                // Consumer disconnected
+                Err(_) => false,
+            };
+            if running && !parked.is_empty() {
+                running = self.unpark(&mut parked)?;
+            }
+            if !running {
+                break;
             }
         }
         Ok(())
     }
 
-    fn handle_get_value(&self) -> Result<Response<T>, Error> {
-        let state = self.state.load();
+    /// Answer the parked requests the source has caught up with
+    ///
+    /// Returns whether to keep running.
+    fn unpark(&self, parked: &mut Parked) -> Result<bool, Error> {
+        let doorbell = self.source.doorbell();
+        for id in doorbell.take_unparked() {
+            if !self.abandon_parked(id, parked) {
+                return Ok(false);
+            }
+        }
+
+        // Those still parked wake us up once the source changes
+        doorbell.park(!parked.is_empty());
+        let generation = self.source.generation();
+        let ready = parked.take_ready(generation, self.source.is_closed());
+        doorbell.park(!parked.is_empty());
+        if ready.is_empty() {
+            return Ok(true);
+        }
+
+        let shared = match self.share {
+            Some(share) => {
+                let ids: Vec<_> = ready.iter().map(|(id, _)| *id).collect();
+                Some((share, self.handle_get_value(&ids)?))
+            }
+            None => None,
+        };
+        for (id, request) in ready {
+            let reply = match &shared {
+                Some((share, reply)) => reply.share(*share),
+                None => self.handle_get_value(&[id])?,
+            };
+            let reply = match request {
+                ParkedRequest::Value => reply,
+                ParkedRequest::Next(_) => reply.versioned(generation),
+            };
+            if !self.reply(id, reply) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Answer with the last `n` values served
+    ///
+    /// Returns whether to keep running.
+    fn answer_history(&self, id: RequestId, n: usize) -> bool {
+        self.reply(id, Reply::History(self.history.last(n)))
+    }
+
+    /// Answer with [Reply::Shared]
+    ///
+    /// Returns whether to keep running.
+    fn answer_shared(&self, id: RequestId) -> Result<bool, Error> {
+        let reply = self.handle_get_arc(id)?;
+        Ok(self.reply(id, reply))
+    }
+
+    /// Answer with [Reply::NotModified] if the source is still that of
+    /// generation `last` and always produces the same value
+    ///
+    /// Returns whether to keep running.
+    fn answer_if_changed(&self, id: RequestId, last: Generation) -> Result<bool, Error> {
+        let generation = self.source.generation();
+        let reply = if generation == last && self.source.load().source.is_fixed() {
+            Reply::NotModified
+        } else {
+            self.handle_get_value(&[id])?.versioned(generation)
+        };
+        Ok(self.reply(id, reply))
+    }
+
+    /// Answer a parked request whose consumer gave up on it, unless it was
+    /// answered already
+    ///
+    /// Returns whether to keep running.
+    fn abandon_parked(&self, id: RequestId, parked: &mut Parked) -> bool {
+        match parked.take(id) {
+            Some(_) => self.reply(id, Reply::NoSource),
+            None => true,
+        }
+    }
+
+    /// Answer `waiting` requests, and those that skipped a full queue
+    ///
+    /// Returns whether to keep running.
+    fn answer(&self, mut waiting: Vec<RequestId>, parked: &mut Parked) -> Result<bool, Error> {
+        waiting.extend(self.coalesced.take());
 
-        match &**state {
-            ValueSource::Static { val, clone } => {
-                let value = self.execute_closure_safely(&mut || clone(val));
-                match value {
-                    Ok(v) => Ok(Response::Value(v)),
-                    Err(_) => Ok(Response::NoSource), // Closure execution failed
+        let Some(share) = self.share else {
+            for id in waiting {
+                let reply = self.handle_get_value(&[id])?;
+                if !self.reply(id, reply) {
+                    // Consumer disconnected
+                    return Ok(false);
                 }
             }
-            ValueSource::Dynamic(closure) => {
-                let value = self.execute_closure_safely(&mut || closure());
-                match value {
-                    Ok(v) => Ok(Response::Value(v)),
-                    Err(_) => Ok(Response::NoSource), // Closure execution failed
+            return Ok(true);
+        };
+
+        if waiting.is_empty() {
+            return Ok(true);
+        }
+
+        let reply = self.handle_get_value(&waiting)?;
+        if matches!(reply, Reply::Failed(Error::Cancelled)) {
+            // Those waiting gave up; requests queued since then need a fresh
+            // evaluation rather than this one
+            for id in waiting {
+                if !self.reply(id, reply.share(share)) {
+                    return Ok(false);
                 }
             }
-            ValueSource::DynamicMut(closure) => {
-                let mut closure = closure.lock().unwrap();
-                let value = self.execute_closure_safely(&mut *closure);
-                match value {
-                    Ok(v) => Ok(Response::Value(v)),
-                    Err(_) => Ok(Response::NoSource), // Closure execution failed
+            return Ok(true);
+        }
+
+        // Everything queued up in the meantime shares this evaluation
+        let mut running = true;
+        loop {
+            match self.request_rx.try_recv() {
+                Ok(Some(Request::GetValue(id))) => waiting.push(id),
+                Ok(Some(Request::GetValueWait(id))) => parked.push(id, ParkedRequest::Value),
+                Ok(Some(Request::GetNext(id, after))) => {
+                    parked.push(id, ParkedRequest::Next(after))
+                }
+                Ok(Some(Request::GetIfChanged(id, last))) => {
+                    if !self.answer_if_changed(id, last)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(Some(Request::GetArc(id))) => {
+                    if !self.answer_shared(id)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(Some(Request::History(id, n))) => {
+                    if !self.answer_history(id, n) {
+                        return Ok(false);
+                    }
+                }
+                Ok(Some(Request::Wake)) => {}
+                Ok(Some(Request::Close)) => {
+                    self.close()?;
+                    running = false;
+                    break;
                 }
+                Ok(None) | Err(_) => break,
+            }
+        }
+        waiting.extend(self.coalesced.take());
+
+        let Some(last) = waiting.pop() else {
+            return Ok(running);
+        };
+        for id in waiting {
+            if !self.reply(id, reply.share(share)) {
+                return Ok(false);
             }
-            ValueSource::None => Ok(Response::NoSource), // No source was ever set
-            ValueSource::Cleared => Ok(Response::Closed), // Channel was closed (source was set then cleared)
         }
+        if !self.reply(last, reply) {
+            return Ok(false);
+        }
+
+        Ok(running)
+    }
+
+    /// Send the reply to request `id`
+    ///
+    /// Returns whether the consumer is still connected.
+    fn reply(&self, id: RequestId, reply: Reply<T>) -> bool {
+        self.response_tx.send(Response { id, reply }).is_ok()
+    }
+
+    /// Evaluate the source for the requests `ids`
+    fn handle_get_value(&self, ids: &[RequestId]) -> Result<Reply<T>, Error> {
+        let _answering = self.source.waiters().answering(ids);
+        self.evaluate_source(false)
+    }
+
+    fn handle_get_arc(&self, id: RequestId) -> Result<Reply<T>, Error> {
+        let _answering = self.source.waiters().answering(&[id]);
+        self.evaluate_source(true)
     }
 
-    fn execute_closure_safely(
-        &self,
-        closure: &mut dyn FnMut() -> T,
-    ) -> Result<T, Box<dyn std::any::Any + Send>> {
-        std::panic::catch_unwind(std::panic::AssertUnwindSafe(closure))
+    /// Evaluate the source, answering with [Reply::Shared] for a shared
+    /// source if `shared`
+    fn evaluate_source(&self, shared: bool) -> Result<Reply<T>, Error> {
+        let slot = self.source.load();
+        if slot.source.is_evaluated() && !self.limiter.admit() {
+            return Ok(self.limiter.over_limit(slot.generation));
+        }
+
+        let mut retries = 0;
+        loop {
+            let slot = self.source.load();
+
+            match self.deadline.evaluate(&slot, self.poison) {
+                Evaluation::Value(value) => {
+                    self.recovery.succeeded(&value, slot.generation);
+                    self.source.settle(&slot, &value);
+                    self.history.record(&value, slot.generation);
+                    self.limiter.succeeded(&value, slot.generation);
+                    return Ok(Reply::Value(value));
+                }
+                Evaluation::Shared(value) if shared => return Ok(Reply::Shared(value)),
+                Evaluation::Shared(_) => return Ok(Reply::Failed(Error::SharedOnly)),
+                Evaluation::NoSource => return Ok(Reply::NoSource),
+                Evaluation::Cancelled => return Ok(Reply::Failed(Error::Cancelled)),
+                Evaluation::Closed => {
+                    self.source.exhausted(&slot);
+                    return Ok(Reply::Closed);
+                }
+                Evaluation::Failed(error) => match self.recovery.retry(retries) {
+                    Some(backoff) => {
+                        retries += 1;
+                        std::thread::sleep(backoff);
+                    }
+                    None => return Ok(self.recovery.failed(&self.source, slot.generation, error)),
+                },
+            }
+        }
     }
 }
 
@@ -178,27 +728,266 @@ where
 {
     /// Get the current value from the producer
     pub fn get(&self) -> Result<T, Error> {
+        self.get_with(self.timeout)
+    }
+
+    /// Get the current value from the producer, giving up after `timeout`
+    pub fn get_timeout(&self, timeout: Duration) -> Result<T, Error> {
+        self.get_with(Some(timeout))
+    }
+
+    /// Get the current value, waiting for the producer to set a source
+    /// instead of failing with [Error::NoSource]
+    ///
+    /// Gives up after `timeout`, if there is one, with [Error::Timeout].
+    pub fn get_wait(&self, timeout: Option<Duration>) -> Result<T, Error> {
+        let deadline = deadline_after(timeout);
+        let id = self.send_uncoalesced(Request::GetValueWait, deadline)?;
+        self.recv_parked(id, deadline)?.into_result()
+    }
+
+    /// Wait for a source newer than generation `after`, and get its value
+    /// along with its generation
+    ///
+    /// Start from `Generation::default()` to wait for the first source. Gives
+    /// up after the default timeout, if there is one, with [Error::Timeout].
+    pub fn get_next(&self, after: Generation) -> Result<(Generation, T), Error> {
+        let deadline = deadline_after(self.timeout);
+        let id = self.send_uncoalesced(|id| Request::GetNext(id, after), deadline)?;
+        match self.recv_parked(id, deadline)? {
+            Reply::Versioned(generation, value) => Ok((generation, value)),
+            // Anything else is a failure
+            reply => reply.into_result().and(Err(Error::InternalError)),
+        }
+    }
+
+    /// Get the current value behind a reference-counted pointer
+    ///
+    /// A source set by `set_shared` hands out its pointer without copying
+    /// the value, other values are put behind a new one.
+    pub fn get_arc(&self) -> Result<Arc<T>, Error>
+    where
+        T: Send + Sync + 'static,
+    {
+        let deadline = deadline_after(self.timeout);
+        let id = self.send_uncoalesced(Request::GetArc, deadline)?;
+        match self.recv_response(id, deadline)? {
+            Reply::Shared(value) => value.downcast().map_err(|_| Error::InternalError),
+            reply => reply.into_result().map(Arc::new),
+        }
+    }
+
+    /// Get the current value along with its generation, unless the source
+    /// is still that of generation `last`
+    ///
+    /// Returns `None` when the source hasn't changed and always produces the
+    /// same value, as one set by `set_static` does. Other sources are
+    /// evaluated every time.
+    pub fn get_if_changed(&self, last: Generation) -> Result<Option<(Generation, T)>, Error> {
+        let deadline = deadline_after(self.timeout);
+        let id = self.send_uncoalesced(|id| Request::GetIfChanged(id, last), deadline)?;
+        match self.recv_response(id, deadline)? {
+            Reply::NotModified => Ok(None),
+            Reply::Versioned(generation, value) => Ok(Some((generation, value))),
+            // Anything else is a failure
+            reply => reply.into_result().and(Err(Error::InternalError)),
+        }
+    }
+
+    /// The last `n` values the producer served, oldest first, along with
+    /// their generations and when they were produced
+    ///
+    /// Empty unless the producer keeps a history, see `with_history`.
+    pub fn history(&self, n: usize) -> Result<Vec<Served<T>>, Error> {
+        let deadline = deadline_after(self.timeout);
+        let id = self.send_uncoalesced(|id| Request::History(id, n), deadline)?;
+        match self.recv_response(id, deadline)? {
+            Reply::History(served) => Ok(served),
+            // Anything else is a failure
+            reply => reply.into_result().and(Err(Error::InternalError)),
+        }
+    }
+
+    /// Run `f` against the current value and return its result
+    ///
+    /// A value set by `set_static` or `set_shared` is borrowed where it is
+    /// kept, without asking the producer or copying it. Other sources are
+    /// pulled as by [get](Self::get).
+    pub fn with<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        T: 'static,
+        F: FnOnce(&T) -> R,
+    {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(Error::ChannelClosed);
+        }
+
+        match self.source.with_fixed(f) {
+            Ok(result) => result,
+            Err(f) => self.get().map(|value| f(&value)),
+        }
+    }
+
+    /// Run a fallible `f` against the current value, as by
+    /// [with](Self::with)
+    ///
+    /// Errors are reported as [Error::MapFailed].
+    pub fn try_with<F, R, E>(&self, f: F) -> Result<R, Error>
+    where
+        T: 'static,
+        F: FnOnce(&T) -> Result<R, E>,
+        E: std::fmt::Display,
+    {
+        self.with(f)?.map_err(|e| Error::MapFailed(e.to_string()))
+    }
+
+    /// Send a request that can't share an answer with others, made by
+    /// `request` from its id
+    fn send_uncoalesced<F>(&self, request: F, deadline: Option<Instant>) -> Result<RequestId, Error>
+    where
+        F: FnOnce(RequestId) -> Request,
+    {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(Error::ChannelClosed);
+        }
+
+        let id = self.mailbox.next_id();
+        let arrival = self.source.waiters().arrive(id);
+        match self.overflow {
+            OverflowPolicy::Fail => self.request_tx.try_send(request(id)),
+            OverflowPolicy::Block | OverflowPolicy::Coalesce => {
+                self.send_until(request(id), deadline)
+            }
+        }
+        .map_err(|e| match e {
+            Error::Busy => Error::Busy,
+            Error::Timeout => Error::Timeout,
+            _ => Error::ProducerDisconnected,
+        })?;
+        arrival.sent();
+        Ok(id)
+    }
+
+    /// Receive the response to a parked request
+    fn recv_parked(&self, id: RequestId, deadline: Option<Instant>) -> Result<Reply<T>, Error> {
+        match self.recv_response(id, deadline) {
+            Err(Error::Timeout) => {
+                // Have the producer answer the parked request right away,
+                // rather than keep it parked for nobody
+                self.source.doorbell().unpark(id);
+                Err(Error::Timeout)
+            }
+            result => result,
+        }
+    }
+
+    fn get_with(&self, timeout: Option<Duration>) -> Result<T, Error> {
+        // Waiting for room in a full queue counts against the timeout too
+        let deadline = deadline_after(timeout);
+        let id = self.send_get(deadline)?;
+        self.recv_response(id, deadline)?.into_result()
+    }
+
+    /// Send a request without waiting for its response, giving up after
+    /// `timeout` or the default timeout should the queue stay full
+    pub(crate) fn send_request(&self, timeout: Option<Duration>) -> Result<RequestId, Error> {
+        self.send_get(deadline_after(timeout.or(self.timeout)))
+    }
+
+    /// Wait for the response to a request sent by [send_request](Self::send_request)
+    pub(crate) fn recv_value(&self, id: RequestId, timeout: Option<Duration>) -> Result<T, Error> {
+        self.recv_response(id, deadline_after(timeout))?
+            .into_result()
+    }
+
+    /// Give up on a request sent by [send_request](Self::send_request),
+    /// discarding its response when it arrives
+    pub(crate) fn abandon(&self, id: RequestId) {
+        self.mailbox.abandon(id);
+        self.source.waiters().left(id);
+    }
+
+    /// The timeout used by [get](Self::get)
+    pub(crate) fn default_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Queue a [Request::GetValue] according to the [OverflowPolicy]
+    fn send_get(&self, deadline: Option<Instant>) -> Result<RequestId, Error> {
         // Check if locally marked as closed
         if self.closed.load(Ordering::Acquire) {
             return Err(Error::ChannelClosed);
         }
 
-        self.request_tx
-            .send(Request::GetValue)
-            .map_err(|_| Error::ProducerDisconnected)?;
+        let id = self.mailbox.next_id();
+        let arrival = self.source.waiters().arrive(id);
+        self.queue_get(id, deadline)?;
+        arrival.sent();
+        Ok(id)
+    }
+
+    fn queue_get(&self, id: RequestId, deadline: Option<Instant>) -> Result<(), Error> {
+        match self.overflow {
+            OverflowPolicy::Coalesce if self.coalesced.is_shared() => {
+                match self.request_tx.try_send(Request::GetValue(id)) {
+                    Err(Error::Busy) => {
+                        self.coalesced.push(id);
+                        // The producer may have drained the queue in the meantime,
+                        // so make sure it wakes up; a full queue wakes it anyway
+                        match self.request_tx.try_send(Request::Wake) {
+                            Ok(()) | Err(Error::Busy) => Ok(()),
+                            Err(_) => Err(Error::ProducerDisconnected),
+                        }
+                    }
+                    other => other.map_err(|_| Error::ProducerDisconnected),
+                }
+            }
+            OverflowPolicy::Block | OverflowPolicy::Coalesce => {
+                match self.send_until(Request::GetValue(id), deadline) {
+                    Err(Error::Timeout) => Err(Error::Timeout),
+                    other => other.map_err(|_| Error::ProducerDisconnected),
+                }
+            }
+            OverflowPolicy::Fail => match self.request_tx.try_send(Request::GetValue(id)) {
+                Err(Error::Busy) => Err(Error::Busy),
+                other => other.map_err(|_| Error::ProducerDisconnected),
+            },
+        }
+    }
 
-        match self.response_rx.recv() {
-            Ok(Response::Value(value)) => Ok(value),
-            Ok(Response::NoSource) => Err(Error::NoSource),
-            Ok(Response::Closed) => Err(Error::ChannelClosed),
-            Err(_) => Err(Error::ProducerDisconnected),
+    /// Send `request`, waiting no later than `deadline` for room in a full
+    /// queue
+    fn send_until(&self, request: Request, deadline: Option<Instant>) -> Result<(), Error> {
+        match deadline {
+            Some(deadline) => self
+                .request_tx
+                .send_timeout(request, deadline.saturating_duration_since(Instant::now())),
+            None => self.request_tx.send(request),
         }
     }
 
+    /// Receive the reply to request `id`, handing those received for other
+    /// requests to their consumers
+    fn recv_response(&self, id: RequestId, deadline: Option<Instant>) -> Result<Reply<T>, Error> {
+        let received = self.mailbox.recv(id, deadline, |timeout| match timeout {
+            Some(timeout) => self.response_rx.recv_timeout(timeout),
+            None => self.response_rx.recv(),
+        });
+        self.source.waiters().left(id);
+
+        received.map_err(|e| match e {
+            Error::Timeout => Error::Timeout,
+            _ => Error::ProducerDisconnected,
+        })
+    }
+
     /// Check if the channel is closed
     pub fn is_closed(&self) -> bool {
-        // Send a test request
-        self.request_tx.send(Request::GetValue).is_err()
+        // Send a test request that isn't answered
+        matches!(
+            self.request_tx.try_send(Request::Wake),
+            Err(Error::ProducerDisconnected)
+        )
     }
 
     /// Close the channel from the consumer side
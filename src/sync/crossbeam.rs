@@ -1,21 +1,11 @@
-use std::sync::Arc;
-
 #[cfg(feature = "sync-crossbeam")]
 use crate::sync::traits::{ChannelError, ChannelReceiver, ChannelSender, ChannelType};
 use crate::types;
-use arc_swap::ArcSwap;
 use crossbeam_channel;
+use std::time::Duration;
 
-type CrossbeamSucker<T> = crate::sync::channel::Sucker<
-    T,
-    CrossbeamSender<types::Request>,
-    CrossbeamReceiver<types::Response<T>>,
->;
-type CrossbeamSourcer<T> = crate::sync::channel::Sourcer<
-    T,
-    CrossbeamReceiver<types::Request>,
-    CrossbeamSender<types::Response<T>>,
->;
+type CrossbeamSucker<T> = crate::sync::channel::SuckerFor<CrossbeamChannel, T>;
+type CrossbeamSourcer<T> = crate::sync::channel::SourcerFor<CrossbeamChannel, T>;
 
 /// Internal sender type for crossbeam backend
 pub struct CrossbeamSender<T>(crossbeam_channel::Sender<T>);
@@ -28,6 +18,20 @@ impl<T> ChannelSender<T> for CrossbeamSender<T> {
             .send(msg)
             .map_err(|_| ChannelError::ProducerDisconnected)
     }
+
+    fn try_send(&self, msg: T) -> Result<(), ChannelError> {
+        self.0.try_send(msg).map_err(|e| match e {
+            crossbeam_channel::TrySendError::Full(_) => ChannelError::Busy,
+            _ => ChannelError::ProducerDisconnected,
+        })
+    }
+
+    fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), ChannelError> {
+        self.0.send_timeout(msg, timeout).map_err(|e| match e {
+            crossbeam_channel::SendTimeoutError::Timeout(_) => ChannelError::Timeout,
+            _ => ChannelError::ProducerDisconnected,
+        })
+    }
 }
 
 impl<T> ChannelReceiver<T> for CrossbeamReceiver<T> {
@@ -36,6 +40,21 @@ impl<T> ChannelReceiver<T> for CrossbeamReceiver<T> {
             .recv()
             .map_err(|_| ChannelError::ProducerDisconnected)
     }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError> {
+        self.0.recv_timeout(timeout).map_err(|e| match e {
+            crossbeam_channel::RecvTimeoutError::Timeout => ChannelError::Timeout,
+            _ => ChannelError::ProducerDisconnected,
+        })
+    }
+
+    fn try_recv(&self) -> Result<Option<T>, ChannelError> {
+        match self.0.try_recv() {
+            Ok(msg) => Ok(Some(msg)),
+            Err(crossbeam_channel::TryRecvError::Empty) => Ok(None),
+            Err(_) => Err(ChannelError::ProducerDisconnected),
+        }
+    }
 }
 
 /// Internal channel type for crossbeam backend
@@ -57,6 +76,13 @@ impl ChannelType for CrossbeamChannel {
         let (tx, rx) = crossbeam_channel::unbounded();
         (CrossbeamSender(tx), CrossbeamReceiver(rx))
     }
+
+    fn create_bounded_request_channel(
+        capacity: usize,
+    ) -> (Self::Sender<types::Request>, Self::Receiver<types::Request>) {
+        let (tx, rx) = crossbeam_channel::bounded(capacity);
+        (CrossbeamSender(tx), CrossbeamReceiver(rx))
+    }
 }
 
 pub struct CrossbeamSuck<T> {
@@ -66,17 +92,16 @@ pub struct CrossbeamSuck<T> {
 impl<T> CrossbeamSuck<T> {
     pub fn pair() -> (CrossbeamSucker<T>, CrossbeamSourcer<T>)
     where
-        T: Clone + Send + 'static,
+        T: Send + 'static,
     {
-        let (request_tx, request_rx) = CrossbeamChannel::create_request_channel();
-        let (response_tx, response_rx) = CrossbeamChannel::create_response_channel::<T>();
-
-        let state = ArcSwap::new(Arc::new(crate::types::ValueSource::None));
-
-        let sucker = crate::sync::channel::Sucker::new(request_tx, response_rx);
-        let sourcer = crate::sync::channel::Sourcer::new(request_rx, response_tx, state);
+        crate::sync::channel::pair::<CrossbeamChannel, T>()
+    }
 
-        (sucker, sourcer)
+    pub fn pair_bounded(capacity: usize) -> (CrossbeamSucker<T>, CrossbeamSourcer<T>)
+    where
+        T: Send + 'static,
+    {
+        crate::sync::channel::pair_bounded::<CrossbeamChannel, T>(capacity)
     }
 }
 
@@ -86,6 +111,8 @@ mod tests {
     use crate::Error;
     use std::thread;
 
+    crate::testing::backend_conformance!(CrossbeamChannel);
+
     #[test]
     fn test_pre_computed_value() {
         let (sucker, sourcer) = CrossbeamSuck::<i32>::pair();
@@ -246,4 +273,51 @@ mod tests {
 
         producer_handle.join().unwrap();
     }
+
+    #[test]
+    fn test_coalesced_requests_share_one_evaluation() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{Arc, Barrier};
+        use std::time::Duration;
+
+        const CONSUMERS: usize = 8;
+
+        let (sucker, sourcer) = CrossbeamSuck::<usize>::pair();
+        let sourcer = sourcer.with_coalescing();
+        let sucker = Arc::new(sucker);
+        let evaluations = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&evaluations);
+        let producer_handle = thread::spawn(move || {
+            sourcer
+                .set(move || {
+                    thread::sleep(Duration::from_millis(50));
+                    counter.fetch_add(1, Ordering::SeqCst) + 1
+                })
+                .unwrap();
+            sourcer.run().unwrap();
+        });
+
+        let barrier = Arc::new(Barrier::new(CONSUMERS));
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let sucker = Arc::clone(&sucker);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    sucker.get().unwrap()
+                })
+            })
+            .collect();
+
+        for consumer in consumers {
+            consumer.join().unwrap();
+        }
+
+        // Everyone got an answer without evaluating once per consumer
+        assert!(evaluations.load(Ordering::SeqCst) < CONSUMERS);
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
 }
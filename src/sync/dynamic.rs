@@ -0,0 +1,178 @@
+use crate::sync::channel::{Sourcer, Sucker, connect};
+use crate::sync::traits::{ChannelReceiver, ChannelSender, ChannelType};
+use crate::types::{Request, Response};
+
+/// Sender with its backend erased
+pub type BoxSender<T> = Box<dyn ChannelSender<T> + Send + Sync>;
+/// Receiver with its backend erased
+pub type BoxReceiver<T> = Box<dyn ChannelReceiver<T> + Send + Sync>;
+
+/// A [Sucker] whose backend is chosen at runtime
+pub type DynSucker<T> = Sucker<T, BoxSender<Request>, BoxReceiver<Response<T>>>;
+/// A [Sourcer] whose backend is chosen at runtime
+pub type DynSourcer<T> = Sourcer<T, BoxReceiver<Request>, BoxSender<Response<T>>>;
+
+/// The bundled channel backends, for selecting one at runtime
+///
+/// Defaults to the first enabled backend, in declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    any(
+        feature = "sync-std",
+        feature = "sync-flume",
+        feature = "sync-crossbeam"
+    ),
+    derive(Default)
+)]
+pub enum Backend {
+    /// [std::sync::mpsc]
+    #[cfg(feature = "sync-std")]
+    #[cfg_attr(feature = "sync-std", default)]
+    Std,
+    /// [flume]
+    #[cfg(feature = "sync-flume")]
+    #[cfg_attr(not(feature = "sync-std"), default)]
+    Flume,
+    /// [crossbeam_channel]
+    #[cfg(feature = "sync-crossbeam")]
+    #[cfg_attr(not(any(feature = "sync-std", feature = "sync-flume")), default)]
+    Crossbeam,
+}
+
+/// Create a [DynSucker]/[DynSourcer] pair over the given [Backend]
+pub fn pair_with<T>(backend: Backend) -> (DynSucker<T>, DynSourcer<T>)
+where
+    T: Send + 'static,
+{
+    backend.pair(None)
+}
+
+/// Create a bounded [DynSucker]/[DynSourcer] pair over the given [Backend]
+///
+/// See [pair_bounded](crate::sync::channel::pair_bounded).
+pub fn pair_with_bounded<T>(backend: Backend, capacity: usize) -> (DynSucker<T>, DynSourcer<T>)
+where
+    T: Send + 'static,
+{
+    backend.pair(Some(capacity))
+}
+
+impl Backend {
+    #[cfg_attr(
+        not(any(
+            feature = "sync-std",
+            feature = "sync-flume",
+            feature = "sync-crossbeam"
+        )),
+        allow(unused_variables)
+    )]
+    fn pair<T>(self, capacity: Option<usize>) -> (DynSucker<T>, DynSourcer<T>)
+    where
+        T: Send + 'static,
+    {
+        match self {
+            #[cfg(feature = "sync-std")]
+            Backend::Std => boxed::<crate::sync::std::StdChannel, T>(capacity),
+            #[cfg(feature = "sync-flume")]
+            Backend::Flume => boxed::<crate::sync::flume::FlumeChannel, T>(capacity),
+            #[cfg(feature = "sync-crossbeam")]
+            Backend::Crossbeam => boxed::<crate::sync::crossbeam::CrossbeamChannel, T>(capacity),
+        }
+    }
+}
+
+#[cfg_attr(
+    not(any(
+        feature = "sync-std",
+        feature = "sync-flume",
+        feature = "sync-crossbeam"
+    )),
+    allow(dead_code)
+)]
+fn boxed<C, T>(capacity: Option<usize>) -> (DynSucker<T>, DynSourcer<T>)
+where
+    C: ChannelType,
+    C::Sender<Request>: Send + Sync + 'static,
+    C::Receiver<Request>: Send + Sync + 'static,
+    C::Sender<Response<T>>: Send + Sync + 'static,
+    C::Receiver<Response<T>>: Send + Sync + 'static,
+{
+    let ((request_tx, request_rx), (response_tx, response_rx)) = match capacity {
+        Some(capacity) => (
+            C::create_bounded_request_channel(capacity.max(1)),
+            C::create_response_channel::<T>(),
+        ),
+        None => (
+            C::create_request_channel(),
+            C::create_response_channel::<T>(),
+        ),
+    };
+
+    connect(
+        Box::new(request_tx) as BoxSender<_>,
+        Box::new(request_rx) as BoxReceiver<_>,
+        Box::new(response_tx) as BoxSender<_>,
+        Box::new(response_rx) as BoxReceiver<_>,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::thread;
+
+    const BACKENDS: &[Backend] = &[
+        #[cfg(feature = "sync-std")]
+        Backend::Std,
+        #[cfg(feature = "sync-flume")]
+        Backend::Flume,
+        #[cfg(feature = "sync-crossbeam")]
+        Backend::Crossbeam,
+    ];
+
+    #[test]
+    fn test_pair_with_every_backend() {
+        for &backend in BACKENDS {
+            let (sucker, sourcer) = pair_with::<i32>(backend);
+
+            let producer_handle = thread::spawn(move || {
+                sourcer.set_static(42).unwrap();
+                sourcer.run().unwrap();
+            });
+
+            assert_eq!(sucker.get(), Ok(42));
+
+            sucker.close().unwrap();
+            producer_handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_dyn_sucker_is_shared_between_threads() {
+        for &backend in BACKENDS {
+            let (sucker, sourcer) = pair_with::<i32>(backend);
+            sourcer.set_static(42).unwrap();
+            let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+            thread::scope(|scope| {
+                for _ in 0..4 {
+                    scope.spawn(|| assert_eq!(sucker.get(), Ok(42)));
+                }
+            });
+
+            sucker.close().unwrap();
+            producer_handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_dyn_producer_disconnection_error() {
+        for &backend in BACKENDS {
+            let (sucker, sourcer) = pair_with::<i32>(backend);
+            drop(sourcer);
+
+            assert!(matches!(sucker.get(), Err(Error::ProducerDisconnected)));
+        }
+    }
+}
@@ -1,18 +1,11 @@
-use std::sync::Arc;
-
 #[cfg(feature = "sync-flume")]
 use crate::sync::traits::{ChannelError, ChannelReceiver, ChannelSender, ChannelType};
 use crate::types;
-use arc_swap::ArcSwap;
 use flume;
+use std::time::Duration;
 
-type FlumeSucker<T> =
-    crate::sync::channel::Sucker<T, FlumeSender<types::Request>, FlumeReceiver<types::Response<T>>>;
-type FlumeSourcer<T> = crate::sync::channel::Sourcer<
-    T,
-    FlumeReceiver<types::Request>,
-    FlumeSender<types::Response<T>>,
->;
+type FlumeSucker<T> = crate::sync::channel::SuckerFor<FlumeChannel, T>;
+type FlumeSourcer<T> = crate::sync::channel::SourcerFor<FlumeChannel, T>;
 
 /// Internal sender type for flume backend  
 pub struct FlumeSender<T>(flume::Sender<T>);
@@ -25,6 +18,20 @@ impl<T> ChannelSender<T> for FlumeSender<T> {
             .send(msg)
             .map_err(|_| ChannelError::ProducerDisconnected)
     }
+
+    fn try_send(&self, msg: T) -> Result<(), ChannelError> {
+        self.0.try_send(msg).map_err(|e| match e {
+            flume::TrySendError::Full(_) => ChannelError::Busy,
+            _ => ChannelError::ProducerDisconnected,
+        })
+    }
+
+    fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), ChannelError> {
+        self.0.send_timeout(msg, timeout).map_err(|e| match e {
+            flume::SendTimeoutError::Timeout(_) => ChannelError::Timeout,
+            _ => ChannelError::ProducerDisconnected,
+        })
+    }
 }
 
 impl<T> ChannelReceiver<T> for FlumeReceiver<T> {
@@ -33,6 +40,21 @@ impl<T> ChannelReceiver<T> for FlumeReceiver<T> {
             .recv()
             .map_err(|_| ChannelError::ProducerDisconnected)
     }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError> {
+        self.0.recv_timeout(timeout).map_err(|e| match e {
+            flume::RecvTimeoutError::Timeout => ChannelError::Timeout,
+            _ => ChannelError::ProducerDisconnected,
+        })
+    }
+
+    fn try_recv(&self) -> Result<Option<T>, ChannelError> {
+        match self.0.try_recv() {
+            Ok(msg) => Ok(Some(msg)),
+            Err(flume::TryRecvError::Empty) => Ok(None),
+            Err(_) => Err(ChannelError::ProducerDisconnected),
+        }
+    }
 }
 
 /// Internal channel type for flume backend
@@ -54,6 +76,13 @@ impl ChannelType for FlumeChannel {
         let (tx, rx) = flume::unbounded();
         (FlumeSender(tx), FlumeReceiver(rx))
     }
+
+    fn create_bounded_request_channel(
+        capacity: usize,
+    ) -> (Self::Sender<types::Request>, Self::Receiver<types::Request>) {
+        let (tx, rx) = flume::bounded(capacity);
+        (FlumeSender(tx), FlumeReceiver(rx))
+    }
 }
 
 pub struct FlumeSuck<T> {
@@ -63,18 +92,16 @@ pub struct FlumeSuck<T> {
 impl<T> FlumeSuck<T> {
     pub fn pair() -> (FlumeSucker<T>, FlumeSourcer<T>)
     where
-        T: Clone + Send + 'static,
+        T: Send + 'static,
     {
-        let (request_tx, request_rx) = FlumeChannel::create_request_channel();
-        let (response_tx, response_rx) = FlumeChannel::create_response_channel::<T>();
-
-        let state = Arc::new(crate::types::ValueSource::None);
-        let state = ArcSwap::new(state);
-
-        let sucker = crate::sync::channel::Sucker::new(request_tx, response_rx);
-        let sourcer = crate::sync::channel::Sourcer::new(request_rx, response_tx, state);
+        crate::sync::channel::pair::<FlumeChannel, T>()
+    }
 
-        (sucker, sourcer)
+    pub fn pair_bounded(capacity: usize) -> (FlumeSucker<T>, FlumeSourcer<T>)
+    where
+        T: Send + 'static,
+    {
+        crate::sync::channel::pair_bounded::<FlumeChannel, T>(capacity)
     }
 }
 
@@ -84,6 +111,8 @@ mod tests {
     use crate::Error;
     use std::thread;
 
+    crate::testing::backend_conformance!(FlumeChannel);
+
     #[test]
     fn test_pre_computed_value() {
         let (sucker, sourcer) = FlumeSuck::<i32>::pair();
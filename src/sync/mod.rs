@@ -1,4 +1,6 @@
 pub mod channel;
+pub mod dynamic;
+pub mod pull;
 pub mod traits;
 
 #[cfg(feature = "sync-crossbeam")]
@@ -16,3 +18,6 @@ pub use crossbeam::CrossbeamSuck;
 
 #[cfg(feature = "sync-std")]
 pub use std::StdSuck;
+
+pub use dynamic::{Backend, DynSourcer, DynSucker, pair_with, pair_with_bounded};
+pub use pull::{FallbackSucker, MappedSucker, Pull, Zip, zip};
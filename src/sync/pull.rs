@@ -0,0 +1,639 @@
+use std::fmt::Display;
+use std::mem::Discriminant;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::sync::channel::Sucker;
+use crate::sync::traits::{ChannelReceiver, ChannelSender};
+use crate::types::{Request, Response, Ticket};
+
+/// The consumer side of a channel, or a projection of one
+///
+/// The trait is sealed: it is implemented by [Sucker] and the adaptors built
+/// on it, and cannot be implemented outside this crate.
+pub trait Pull: sealed::Sealed {
+    type Item;
+
+    /// Get the current value from the producer
+    fn get(&self) -> Result<Self::Item, Error>;
+
+    /// Get the current value from the producer, giving up after `timeout`
+    fn get_timeout(&self, timeout: Duration) -> Result<Self::Item, Error>;
+
+    /// Check if the channel is closed
+    fn is_closed(&self) -> bool;
+
+    /// Close the channel from the consumer side
+    fn close(&self) -> Result<(), Error>;
+
+    /// Get the current value, or `default` if the pull fails
+    fn get_or(&self, default: Self::Item) -> Self::Item {
+        self.get().unwrap_or(default)
+    }
+
+    /// Get the current value, or compute one from the error if the pull fails
+    fn get_or_else<F>(&self, f: F) -> Self::Item
+    where
+        F: FnOnce(Error) -> Self::Item,
+    {
+        self.get().unwrap_or_else(f)
+    }
+
+    /// Send a request without waiting for its response, see [Zip], giving
+    /// up after `timeout` or the default timeout should the queue stay full
+    #[doc(hidden)]
+    fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error>;
+
+    /// Wait for the response to a [request](Self::request), giving up after
+    /// `timeout` or the default timeout if there is none
+    #[doc(hidden)]
+    fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<Self::Item, Error>;
+
+    /// Give up on a [request](Self::request) without waiting for its response
+    #[doc(hidden)]
+    fn abandon(&self, ticket: Ticket);
+
+    /// Convert every value pulled through this sucker with `f`
+    fn map<U, F>(self, f: F) -> MappedSucker<Self, impl Fn(Self::Item) -> Result<U, Error>>
+    where
+        Self: Sized,
+        F: Fn(Self::Item) -> U,
+    {
+        MappedSucker::new(self, move |value| Ok(f(value)))
+    }
+
+    /// Convert every value pulled through this sucker with `f`, failing the
+    /// pull with [Error::MapFailed] when `f` does
+    fn try_map<U, E, F>(self, f: F) -> MappedSucker<Self, impl Fn(Self::Item) -> Result<U, Error>>
+    where
+        Self: Sized,
+        E: Display,
+        F: Fn(Self::Item) -> Result<U, E>,
+    {
+        MappedSucker::new(self, move |value| {
+            f(value).map_err(|e| Error::MapFailed(e.to_string()))
+        })
+    }
+
+    /// Pull from `other` when a pull through this sucker fails with
+    /// [Error::NoSource] or [Error::ProducerDisconnected], see
+    /// [FallbackSucker::fallback_on] for other errors
+    fn or<S>(self, other: S) -> FallbackSucker<Self, S>
+    where
+        Self: Sized,
+        S: Pull<Item = Self::Item>,
+    {
+        FallbackSucker::new(self, other)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+impl<T, ST, SR> sealed::Sealed for Sucker<T, ST, SR>
+where
+    ST: ChannelSender<Request>,
+    SR: ChannelReceiver<Response<T>>,
+{
+}
+
+impl<T, ST, SR> Pull for Sucker<T, ST, SR>
+where
+    ST: ChannelSender<Request>,
+    SR: ChannelReceiver<Response<T>>,
+{
+    type Item = T;
+
+    fn get(&self) -> Result<T, Error> {
+        Sucker::get(self)
+    }
+
+    fn get_timeout(&self, timeout: Duration) -> Result<T, Error> {
+        Sucker::get_timeout(self, timeout)
+    }
+
+    fn is_closed(&self) -> bool {
+        Sucker::is_closed(self)
+    }
+
+    fn close(&self) -> Result<(), Error> {
+        Sucker::close(self)
+    }
+
+    fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        self.send_request(timeout).map(Ticket::Request)
+    }
+
+    fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<T, Error> {
+        match ticket {
+            Ticket::Request(id) => self.recv_value(id, timeout.or(self.default_timeout())),
+            _ => Err(Error::InternalError),
+        }
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        if let Ticket::Request(id) = ticket {
+            Sucker::abandon(self, id)
+        }
+    }
+}
+
+impl<P: Pull + ?Sized> sealed::Sealed for &P {}
+
+impl<P> Pull for &P
+where
+    P: Pull + ?Sized,
+{
+    type Item = P::Item;
+
+    fn get(&self) -> Result<P::Item, Error> {
+        (**self).get()
+    }
+
+    fn get_timeout(&self, timeout: Duration) -> Result<P::Item, Error> {
+        (**self).get_timeout(timeout)
+    }
+
+    fn is_closed(&self) -> bool {
+        (**self).is_closed()
+    }
+
+    fn close(&self) -> Result<(), Error> {
+        (**self).close()
+    }
+
+    fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        (**self).request(timeout)
+    }
+
+    fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<P::Item, Error> {
+        (**self).receive(ticket, timeout)
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        (**self).abandon(ticket)
+    }
+}
+
+/// A sucker whose values are converted on the way out, see [Pull::map]
+pub struct MappedSucker<S, F> {
+    inner: S,
+    f: F,
+}
+
+impl<S, F> MappedSucker<S, F> {
+    fn new(inner: S, f: F) -> Self {
+        Self { inner, f }
+    }
+
+    /// The sucker values are pulled through
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Give back the sucker values are pulled through
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, F> sealed::Sealed for MappedSucker<S, F> {}
+
+impl<S, F, U> Pull for MappedSucker<S, F>
+where
+    S: Pull,
+    F: Fn(S::Item) -> Result<U, Error>,
+{
+    type Item = U;
+
+    fn get(&self) -> Result<U, Error> {
+        self.inner.get().and_then(&self.f)
+    }
+
+    fn get_timeout(&self, timeout: Duration) -> Result<U, Error> {
+        self.inner.get_timeout(timeout).and_then(&self.f)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    fn close(&self) -> Result<(), Error> {
+        self.inner.close()
+    }
+
+    fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        self.inner.request(timeout)
+    }
+
+    fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<U, Error> {
+        self.inner.receive(ticket, timeout).and_then(&self.f)
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        self.inner.abandon(ticket)
+    }
+}
+
+/// A sucker that falls back to another when a pull fails, see [Pull::or]
+pub struct FallbackSucker<P, S> {
+    primary: P,
+    secondary: S,
+    triggers: Vec<Discriminant<Error>>,
+}
+
+impl<P, S> FallbackSucker<P, S> {
+    fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            triggers: Vec::new(),
+        }
+        .fallback_on([Error::NoSource, Error::ProducerDisconnected])
+    }
+
+    /// Fall back on these errors instead, compared by variant only, so
+    /// `Error::SourceFailed(String::new())` matches any failed source
+    pub fn fallback_on(mut self, errors: impl IntoIterator<Item = Error>) -> Self {
+        self.triggers = errors
+            .into_iter()
+            .map(|error| std::mem::discriminant(&error))
+            .collect();
+        self
+    }
+
+    fn falls_back(&self, error: &Error) -> bool {
+        self.triggers.contains(&std::mem::discriminant(error))
+    }
+
+    /// Give back both suckers
+    pub fn into_inner(self) -> (P, S) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl<P, S> sealed::Sealed for FallbackSucker<P, S> {}
+
+impl<P, S> Pull for FallbackSucker<P, S>
+where
+    P: Pull,
+    S: Pull<Item = P::Item>,
+{
+    type Item = P::Item;
+
+    fn get(&self) -> Result<P::Item, Error> {
+        let ticket = self.request(None)?;
+        self.receive(ticket, None)
+    }
+
+    fn get_timeout(&self, timeout: Duration) -> Result<P::Item, Error> {
+        let deadline = Instant::now() + timeout;
+        let ticket = self.request(Some(timeout))?;
+        self.receive(
+            ticket,
+            Some(deadline.saturating_duration_since(Instant::now())),
+        )
+    }
+
+    fn is_closed(&self) -> bool {
+        self.primary.is_closed() && self.secondary.is_closed()
+    }
+
+    fn close(&self) -> Result<(), Error> {
+        let primary = self.primary.close();
+        let secondary = self.secondary.close();
+        primary.and(secondary)
+    }
+
+    fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        match self.primary.request(timeout) {
+            Err(error) if self.falls_back(&error) => {
+                let ticket = self.secondary.request(timeout)?;
+                Ok(Ticket::Diverted(Box::new(ticket)))
+            }
+            result => result,
+        }
+    }
+
+    fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<P::Item, Error> {
+        if let Ticket::Diverted(ticket) = ticket {
+            return self.secondary.receive(*ticket, timeout);
+        }
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        match self.primary.receive(ticket, timeout) {
+            Err(error) if self.falls_back(&error) => match deadline {
+                Some(deadline) => self
+                    .secondary
+                    .get_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self.secondary.get(),
+            },
+            result => result,
+        }
+    }
+
+    fn abandon(&self, ticket: Ticket) {
+        match ticket {
+            Ticket::Diverted(ticket) => self.secondary.abandon(*ticket),
+            ticket => self.primary.abandon(ticket),
+        }
+    }
+}
+
+/// Pull from two suckers at once, see [Zip]
+pub fn zip<A: Pull, B: Pull>(a: A, b: B) -> Zip<(A, B)> {
+    Zip::new((a, b))
+}
+
+/// Pull from every sucker in a tuple of two to five at once
+///
+/// All requests are sent before waiting on any response, so the producers
+/// work concurrently and a pull takes as long as the slowest of them.
+///
+/// ```
+/// use suck::sync::StdSuck;
+/// use suck::{Pull, join};
+///
+/// let (name, name_source) = StdSuck::<&str>::pair();
+/// let (answer, answer_source) = StdSuck::<i32>::pair();
+/// name_source.set_static("answer")?;
+/// answer_source.set_static(42)?;
+/// let producers = [
+///     std::thread::spawn(move || name_source.run()),
+///     std::thread::spawn(move || answer_source.run()),
+/// ];
+///
+/// assert_eq!(join!(name, answer), Ok(("answer", 42)));
+///
+/// suck::zip(name, answer).close()?;
+/// # for producer in producers { producer.join().unwrap()?; }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct Zip<S>(S);
+
+impl<S> Zip<S> {
+    /// Zip a tuple of two to five suckers
+    pub fn new(suckers: S) -> Self {
+        Self(suckers)
+    }
+
+    /// Give back the suckers
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+/// Pull from several suckers at once, returning a tuple of their values
+///
+/// Takes two to five suckers by reference, see [Zip].
+#[macro_export]
+macro_rules! join {
+    ($($sucker:expr),+ $(,)?) => {
+        $crate::Pull::get(&$crate::Zip::new(($(&$sucker,)+)))
+    };
+}
+
+/// The part of [Pull] a [Zip] needs to send its requests
+trait Outstanding {
+    fn send_request(&self, timeout: Option<Duration>) -> Result<Ticket, Error>;
+    fn abandon_request(&self, ticket: Ticket);
+}
+
+impl<P: Pull> Outstanding for P {
+    fn send_request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+        self.request(timeout)
+    }
+
+    fn abandon_request(&self, ticket: Ticket) {
+        self.abandon(ticket)
+    }
+}
+
+impl<S> sealed::Sealed for Zip<S> {}
+
+macro_rules! impl_zip {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Pull),+> Pull for Zip<($($name,)+)> {
+            type Item = ($($name::Item,)+);
+
+            fn get(&self) -> Result<Self::Item, Error> {
+                let ticket = self.request(None)?;
+                self.receive(ticket, None)
+            }
+
+            fn get_timeout(&self, timeout: Duration) -> Result<Self::Item, Error> {
+                let deadline = Instant::now() + timeout;
+                let ticket = self.request(Some(timeout))?;
+                self.receive(ticket, Some(deadline.saturating_duration_since(Instant::now())))
+            }
+
+            fn is_closed(&self) -> bool {
+                $(self.0.$index.is_closed())||+
+            }
+
+            fn close(&self) -> Result<(), Error> {
+                let results = ($(self.0.$index.close(),)+);
+                $(results.$index?;)+
+                Ok(())
+            }
+
+            fn request(&self, timeout: Option<Duration>) -> Result<Ticket, Error> {
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                let remaining =
+                    || deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+                let suckers: &[&dyn Outstanding] = &[$(&self.0.$index),+];
+                let mut tickets = Vec::with_capacity(suckers.len());
+                for sucker in suckers {
+                    match sucker.send_request(remaining()) {
+                        Ok(ticket) => tickets.push(ticket),
+                        Err(error) => {
+                            for (sucker, ticket) in suckers.iter().zip(tickets) {
+                                sucker.abandon_request(ticket);
+                            }
+                            return Err(error);
+                        }
+                    }
+                }
+                Ok(Ticket::Many(tickets))
+            }
+
+            fn receive(&self, ticket: Ticket, timeout: Option<Duration>) -> Result<Self::Item, Error> {
+                let Ticket::Many(tickets) = ticket else {
+                    return Err(Error::InternalError);
+                };
+                let mut tickets = tickets.into_iter();
+                let mut next = || tickets.next().ok_or(Error::InternalError);
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                let remaining =
+                    || deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+                // Receive every response, even after a failure, so none is
+                // left waiting for nobody
+                let results = ($(next().and_then(|ticket| self.0.$index.receive(ticket, remaining())),)+);
+                Ok(($(results.$index?,)+))
+            }
+
+            fn abandon(&self, ticket: Ticket) {
+                if let Ticket::Many(tickets) = ticket {
+                    let mut tickets = tickets.into_iter();
+                    $(if let Some(ticket) = tickets.next() {
+                        self.0.$index.abandon(ticket);
+                    })+
+                }
+            }
+        }
+    };
+}
+
+impl_zip!(A 0, B 1);
+impl_zip!(A 0, B 1, C 2);
+impl_zip!(A 0, B 1, C 2, D 3);
+impl_zip!(A 0, B 1, C 2, D 3, E 4);
+
+#[cfg(all(test, feature = "sync-std"))]
+mod tests {
+    use super::*;
+    use crate::sync::std::StdSuck;
+    use std::thread;
+
+    struct Config {
+        name: &'static str,
+        timeout: Duration,
+    }
+
+    fn first_char<P: Pull<Item = &'static str>>(sucker: &P) -> Result<char, Error> {
+        sucker.get().map(|name| name.chars().next().unwrap())
+    }
+
+    #[test]
+    fn test_map() {
+        let (sucker, sourcer) = StdSuck::<Config>::pair();
+        let sucker = sucker.map(|config| config.name);
+
+        let producer_handle = thread::spawn(move || {
+            sourcer
+                .set(|| Config {
+                    name: "suck",
+                    timeout: Duration::from_secs(1),
+                })
+                .unwrap();
+            sourcer.run().unwrap();
+        });
+
+        // A projection works wherever a sucker does
+        assert_eq!(first_char(&sucker), Ok('s'));
+
+        let sucker = sucker.into_inner().map(|config| config.timeout);
+        assert_eq!(sucker.get(), Ok(Duration::from_secs(1)));
+
+        sucker.close().unwrap();
+        assert_eq!(sucker.get(), Err(Error::ChannelClosed));
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_try_map() {
+        let (sucker, sourcer) = StdSuck::<&'static str>::pair();
+        let sucker = sucker.try_map(str::parse::<i32>).map(|n| n * 2);
+
+        let producer_handle = thread::spawn(move || {
+            let mut values = ["21", "twenty-one"].into_iter();
+            sourcer.set_mut(move || values.next().unwrap()).unwrap();
+            sourcer.run().unwrap();
+        });
+
+        assert_eq!(sucker.get(), Ok(42));
+        assert_eq!(
+            sucker.get(),
+            Err(Error::MapFailed("invalid digit found in string".to_owned()))
+        );
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_zip_pulls_concurrently() {
+        let suckers: Vec<_> = (0..3)
+            .map(|n| {
+                let (sucker, sourcer) = StdSuck::<i32>::pair();
+                sourcer
+                    .set(move || {
+                        thread::sleep(Duration::from_millis(100));
+                        n
+                    })
+                    .unwrap();
+                thread::spawn(move || sourcer.run().unwrap());
+                sucker
+            })
+            .collect();
+        let [a, b, c] = <[_; 3]>::try_from(suckers).ok().unwrap();
+
+        let start = Instant::now();
+        assert_eq!(crate::join!(a, b, c), Ok((0, 1, 2)));
+        assert!(start.elapsed() < Duration::from_millis(250));
+
+        let pair = zip(a.map(|n| n * 10), b);
+        assert_eq!(pair.get(), Ok((0, 1)));
+        pair.close().unwrap();
+        c.close().unwrap();
+    }
+
+    #[test]
+    fn test_zip_failure_keeps_suckers_in_step() {
+        let (a, a_source) = StdSuck::<i32>::pair();
+        let (b, b_source) = StdSuck::<i32>::pair();
+        a_source.set_static(1).unwrap();
+        let producers = [
+            thread::spawn(move || a_source.run().unwrap()),
+            thread::spawn(move || b_source.run().unwrap()),
+        ];
+
+        assert_eq!(crate::join!(a, b), Err(Error::NoSource));
+        assert_eq!(a.get(), Ok(1));
+
+        b.close().unwrap();
+        assert_eq!(crate::join!(a, b), Err(Error::ChannelClosed));
+        assert_eq!(a.get(), Ok(1));
+
+        a.close().unwrap();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_or_falls_back() {
+        let (primary, primary_source) = StdSuck::<i32>::pair();
+        let (secondary, secondary_source) = StdSuck::<i32>::pair();
+        secondary_source.set_static(2).unwrap();
+        let producers = [
+            thread::spawn(move || primary_source.run().unwrap()),
+            thread::spawn(move || secondary_source.run().unwrap()),
+        ];
+
+        let sucker = (&primary).or(&secondary);
+        assert_eq!(sucker.get(), Ok(2));
+
+        // Only the chosen errors trigger the fallback
+        let sucker = sucker.fallback_on([Error::ChannelClosed]);
+        assert_eq!(sucker.get(), Err(Error::NoSource));
+        primary.close().unwrap();
+        assert_eq!(sucker.get(), Ok(2));
+
+        assert_eq!(primary.get_or(1), 1);
+        assert_eq!(
+            primary.get_or_else(|e| (e == Error::ChannelClosed) as i32),
+            1
+        );
+        assert_eq!(secondary.get_or(1), 2);
+
+        secondary.close().unwrap();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert!(sucker.is_closed());
+    }
+}
@@ -1,35 +1,173 @@
-use arc_swap::ArcSwap;
-
 use crate::sync::traits::{ChannelError, ChannelReceiver, ChannelSender, ChannelType};
 use crate::types;
-use std::sync::Arc;
 #[cfg(feature = "sync-std")]
 use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+type StdSucker<T> = crate::sync::channel::SuckerFor<StdChannel, T>;
+type StdSourcer<T> = crate::sync::channel::SourcerFor<StdChannel, T>;
+
+/// Wakes timed sends waiting on a full bounded queue, as [mpsc::SyncSender]
+/// has no timed send
+#[derive(Default)]
+struct Space {
+    /// How many times the receiver has made room, by taking a message or by
+    /// going away
+    freed: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl Space {
+    fn lock(&self) -> MutexGuard<'_, u64> {
+        self.freed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn seen(&self) -> u64 {
+        *self.lock()
+    }
+
+    fn free(&self) {
+        *self.lock() += 1;
+        self.changed.notify_all();
+    }
+
+    /// Wait up to `timeout` for room to be made after `seen`
+    fn wait(&self, seen: u64, timeout: Duration) {
+        let _ = self
+            .changed
+            .wait_timeout_while(self.lock(), timeout, |freed| *freed == seen)
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+}
 
-type StdSucker<T> =
-    crate::sync::channel::Sucker<T, StdSender<types::Request>, StdReceiver<types::Response<T>>>;
-type StdSourcer<T> =
-    crate::sync::channel::Sourcer<T, StdReceiver<types::Request>, StdSender<types::Response<T>>>;
+/// The receiver's hold on a bounded queue's [Space], freeing it on drop so
+/// waiting sends see the disconnect
+struct Freer(Arc<Space>);
+
+impl Drop for Freer {
+    fn drop(&mut self) {
+        self.0.free();
+    }
+}
 
 /// Internal sender type for std backend
-pub struct StdSender<T>(mpsc::Sender<T>);
+pub struct StdSender<T>(StdSenderKind<T>);
+
+enum StdSenderKind<T> {
+    Unbounded(mpsc::Sender<T>),
+    Bounded(mpsc::SyncSender<T>, Arc<Space>),
+}
 /// Internal receiver type for std backend
-pub struct StdReceiver<T>(mpsc::Receiver<T>);
+///
+/// [mpsc::Receiver] isn't [Sync], so it sits behind a lock for a sucker to
+/// be shared between consumer threads.
+pub struct StdReceiver<T> {
+    rx: Mutex<mpsc::Receiver<T>>,
+    // Declared after `rx` so waiting sends are woken once it has gone
+    space: Option<Freer>,
+}
+
+impl<T> StdReceiver<T> {
+    fn new(rx: mpsc::Receiver<T>) -> Self {
+        Self {
+            rx: Mutex::new(rx),
+            space: None,
+        }
+    }
+
+    fn bounded(rx: mpsc::Receiver<T>, space: Arc<Space>) -> Self {
+        Self {
+            rx: Mutex::new(rx),
+            space: Some(Freer(space)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, mpsc::Receiver<T>> {
+        self.rx.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn took<E>(&self, received: Result<T, E>) -> Result<T, E> {
+        if let (Ok(_), Some(Freer(space))) = (&received, &self.space) {
+            space.free();
+        }
+        received
+    }
+}
 
 impl<T> ChannelSender<T> for StdSender<T> {
     fn send(&self, msg: T) -> Result<(), ChannelError> {
-        self.0
-            .send(msg)
-            .map_err(|_| ChannelError::ProducerDisconnected)
+        match &self.0 {
+            StdSenderKind::Unbounded(tx) => {
+                tx.send(msg).map_err(|_| ChannelError::ProducerDisconnected)
+            }
+            StdSenderKind::Bounded(tx, _) => {
+                tx.send(msg).map_err(|_| ChannelError::ProducerDisconnected)
+            }
+        }
+    }
+
+    fn try_send(&self, msg: T) -> Result<(), ChannelError> {
+        match &self.0 {
+            StdSenderKind::Unbounded(tx) => {
+                tx.send(msg).map_err(|_| ChannelError::ProducerDisconnected)
+            }
+            StdSenderKind::Bounded(tx, _) => tx.try_send(msg).map_err(|e| match e {
+                mpsc::TrySendError::Full(_) => ChannelError::Busy,
+                _ => ChannelError::ProducerDisconnected,
+            }),
+        }
+    }
+
+    fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), ChannelError> {
+        let StdSenderKind::Bounded(tx, space) = &self.0 else {
+            return self.send(msg);
+        };
+        let deadline = Instant::now() + timeout;
+        let mut msg = msg;
+        loop {
+            let seen = space.seen();
+            match tx.try_send(msg) {
+                Ok(()) => return Ok(()),
+                Err(mpsc::TrySendError::Full(rejected)) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(ChannelError::Timeout);
+                    }
+                    msg = rejected;
+                    space.wait(seen, remaining);
+                }
+                Err(mpsc::TrySendError::Disconnected(_)) => {
+                    return Err(ChannelError::ProducerDisconnected);
+                }
+            }
+        }
     }
 }
 
 impl<T> ChannelReceiver<T> for StdReceiver<T> {
     fn recv(&self) -> Result<T, ChannelError> {
-        self.0
-            .recv()
+        let received = self.lock().recv();
+        self.took(received)
             .map_err(|_| ChannelError::ProducerDisconnected)
     }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError> {
+        let received = self.lock().recv_timeout(timeout);
+        self.took(received).map_err(|e| match e {
+            mpsc::RecvTimeoutError::Timeout => ChannelError::Timeout,
+            _ => ChannelError::ProducerDisconnected,
+        })
+    }
+
+    fn try_recv(&self) -> Result<Option<T>, ChannelError> {
+        let received = self.lock().try_recv();
+        match self.took(received) {
+            Ok(msg) => Ok(Some(msg)),
+            Err(mpsc::TryRecvError::Empty) => Ok(None),
+            Err(_) => Err(ChannelError::ProducerDisconnected),
+        }
+    }
 }
 
 /// Internal channel type for std backend
@@ -41,7 +179,10 @@ impl ChannelType for StdChannel {
 
     fn create_request_channel() -> (Self::Sender<types::Request>, Self::Receiver<types::Request>) {
         let (tx, rx) = mpsc::channel();
-        (StdSender(tx), StdReceiver(rx))
+        (
+            StdSender(StdSenderKind::Unbounded(tx)),
+            StdReceiver::new(rx),
+        )
     }
 
     fn create_response_channel<T>() -> (
@@ -49,7 +190,21 @@ impl ChannelType for StdChannel {
         Self::Receiver<types::Response<T>>,
     ) {
         let (tx, rx) = mpsc::channel();
-        (StdSender(tx), StdReceiver(rx))
+        (
+            StdSender(StdSenderKind::Unbounded(tx)),
+            StdReceiver::new(rx),
+        )
+    }
+
+    fn create_bounded_request_channel(
+        capacity: usize,
+    ) -> (Self::Sender<types::Request>, Self::Receiver<types::Request>) {
+        let (tx, rx) = mpsc::sync_channel(capacity);
+        let space = Arc::new(Space::default());
+        (
+            StdSender(StdSenderKind::Bounded(tx, space.clone())),
+            StdReceiver::bounded(rx, space),
+        )
     }
 }
 
@@ -60,18 +215,16 @@ pub struct StdSuck<T> {
 impl<T> StdSuck<T> {
     pub fn pair() -> (StdSucker<T>, StdSourcer<T>)
     where
-        T: Clone + Send + 'static,
+        T: Send + 'static,
     {
-        let (request_tx, request_rx) = StdChannel::create_request_channel();
-        let (response_tx, response_rx) = StdChannel::create_response_channel::<T>();
-
-        let state = Arc::new(crate::types::ValueSource::None);
-        let state = ArcSwap::new(state);
-
-        let sucker = crate::sync::channel::Sucker::new(request_tx, response_rx);
-        let sourcer = crate::sync::channel::Sourcer::new(request_rx, response_tx, state);
+        crate::sync::channel::pair::<StdChannel, T>()
+    }
 
-        (sucker, sourcer)
+    pub fn pair_bounded(capacity: usize) -> (StdSucker<T>, StdSourcer<T>)
+    where
+        T: Send + 'static,
+    {
+        crate::sync::channel::pair_bounded::<StdChannel, T>(capacity)
     }
 }
 
@@ -82,6 +235,8 @@ mod tests {
     use crate::sync::traits::ChannelType;
     use std::thread;
 
+    crate::testing::backend_conformance!(StdChannel);
+
     #[derive(Debug)]
     struct PanicOnClone;
 
@@ -236,14 +391,20 @@ mod tests {
         let (response_tx, response_rx) = StdChannel::create_response_channel::<i32>();
         drop(response_rx);
 
-        let state = Arc::new(crate::types::ValueSource::None);
-        let state = ArcSwap::new(state);
-        let sourcer = crate::sync::channel::Sourcer::new(request_rx, response_tx, state);
+        let sourcer = crate::sync::channel::Sourcer::new(
+            request_rx,
+            response_tx,
+            Default::default(),
+            Default::default(),
+        );
         sourcer.set_static(42).unwrap();
 
         let producer_handle = thread::spawn(move || sourcer.run().unwrap());
 
-        request_tx.send(crate::types::Request::GetValue).unwrap();
+        let id = crate::types::RequestId(0);
+        request_tx
+            .send(crate::types::Request::GetValue(id))
+            .unwrap();
 
         producer_handle.join().unwrap();
     }
@@ -254,9 +415,12 @@ mod tests {
         let (response_tx, _response_rx) = StdChannel::create_response_channel::<i32>();
         drop(request_tx);
 
-        let state = Arc::new(crate::types::ValueSource::None);
-        let state = ArcSwap::new(state);
-        let sourcer = crate::sync::channel::Sourcer::new(request_rx, response_tx, state);
+        let sourcer = crate::sync::channel::Sourcer::new(
+            request_rx,
+            response_tx,
+            Default::default(),
+            Default::default(),
+        );
 
         sourcer.run().unwrap();
     }
@@ -355,4 +519,818 @@ mod tests {
 
         producer_handle.join().unwrap();
     }
+
+    #[test]
+    fn test_fallible_source_error() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+
+        let producer_handle = thread::spawn(move || {
+            sourcer.set_fallible(|| Err("disk on fire")).unwrap();
+            sourcer.run().unwrap();
+        });
+
+        let result = sucker.get();
+        assert_eq!(result, Err(Error::SourceFailed("disk on fire".to_owned())));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_failure_policy_retry() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let sourcer = sourcer.with_failure_policy(crate::FailurePolicy::RetryN {
+            attempts: 2,
+            backoff: std::time::Duration::from_millis(1),
+        });
+
+        let producer_handle = thread::spawn(move || {
+            let mut calls = 0;
+            sourcer
+                .set_mut(move || {
+                    calls += 1;
+                    if calls < 3 {
+                        panic!("intentional panic from flaky source");
+                    }
+                    calls
+                })
+                .unwrap();
+            sourcer.run().unwrap();
+        });
+
+        // Two failures are retried away, the third call succeeds
+        assert_eq!(sucker.get(), Ok(3));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_failure_policy_serve_last_good() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let sourcer = sourcer.with_failure_policy(crate::FailurePolicy::ServeLastGood);
+        let handle = sourcer.handle();
+
+        let producer_handle = thread::spawn(move || {
+            let mut calls = 0;
+            sourcer
+                .set_fallible_mut(move || {
+                    calls += 1;
+                    if calls == 1 { Ok(1) } else { Err("gone") }
+                })
+                .unwrap();
+            sourcer.run().unwrap();
+        });
+
+        assert_eq!(sucker.get(), Ok(1));
+        assert_eq!(sucker.get(), Ok(1));
+
+        // A value of the previous source isn't served for a new one
+        handle.set_fallible(|| Err::<i32, _>("new")).unwrap();
+        assert_eq!(sucker.get(), Err(Error::SourceFailed("new".to_string())));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_failure_policy_close_channel() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let sourcer = sourcer.with_failure_policy(crate::FailurePolicy::CloseChannel);
+
+        let producer_handle = thread::spawn(move || {
+            sourcer
+                .set(|| -> i32 {
+                    panic!("intentional panic from Fn source");
+                })
+                .unwrap();
+            sourcer.run().unwrap();
+        });
+
+        assert_eq!(sucker.get(), Err(Error::ChannelClosed));
+        assert_eq!(sucker.get(), Err(Error::ChannelClosed));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_poisoned_source_recovers() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+
+        let producer_handle = thread::spawn(move || {
+            sourcer.handle().install(crate::types::poisoned_mut(|| 42));
+            sourcer.run().unwrap();
+        });
+
+        assert_eq!(sucker.get(), Ok(42));
+        assert_eq!(sucker.get(), Ok(42));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_poisoned_source_fails() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let sourcer = sourcer.with_poison_policy(crate::PoisonPolicy::Fail);
+
+        let producer_handle = thread::spawn(move || {
+            sourcer.handle().install(crate::types::poisoned_mut(|| 42));
+            sourcer.run().unwrap();
+        });
+
+        assert_eq!(sucker.get(), Err(Error::SourcePoisoned));
+        // The producer keeps running and stays poisoned
+        assert_eq!(sucker.get(), Err(Error::SourcePoisoned));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_get_wait_parks_until_source_set() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let handle = sourcer.handle();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        let timeout = std::time::Duration::from_millis(20);
+        assert_eq!(sucker.get_wait(Some(timeout)), Err(Error::Timeout));
+        assert_eq!(sucker.get(), Err(Error::NoSource));
+
+        let setter = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(50));
+            handle.set_static(42).unwrap();
+        });
+        assert_eq!(sucker.get_wait(None), Ok(42));
+        setter.join().unwrap();
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_get_next_long_polls() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let sucker = sucker.with_default_timeout(std::time::Duration::from_secs(5));
+        let handle = sourcer.handle();
+        sourcer.set_static(1).unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        let (first, value) = sucker.get_next(crate::Generation::default()).unwrap();
+        assert_eq!(value, 1);
+
+        let setter = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(50));
+            handle.set_static(2).unwrap();
+        });
+        let (second, value) = sucker.get_next(first).unwrap();
+        assert_eq!(value, 2);
+        assert!(second > first);
+        setter.join().unwrap();
+
+        // Nothing newer turns up, and the abandoned request stays out of the way
+        let sucker = sucker.with_default_timeout(std::time::Duration::from_millis(20));
+        assert_eq!(sucker.get_next(second), Err(Error::Timeout));
+        assert_eq!(sucker.get(), Ok(2));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_get_if_changed() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let handle = sourcer.handle();
+        sourcer.set_static(1).unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        let (generation, value) = sucker
+            .get_if_changed(crate::Generation::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(value, 1);
+        assert_eq!(sucker.get_if_changed(generation), Ok(None));
+
+        // Closures are evaluated every time
+        handle.set(|| 2).unwrap();
+        let (generation, value) = sucker.get_if_changed(generation).unwrap().unwrap();
+        assert_eq!(value, 2);
+        assert_eq!(sucker.get_if_changed(generation), Ok(Some((generation, 2))));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_shared_sucker_gets_its_own_responses() {
+        let (sucker, sourcer) = StdSuck::<i32>::pair();
+        let sourcer = sourcer.with_history(1);
+        sourcer
+            .set(|| {
+                thread::sleep(Duration::from_millis(1));
+                42
+            })
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        thread::scope(|scope| {
+            scope.spawn(|| (0..50).for_each(|_| assert_eq!(sucker.get(), Ok(42))));
+            scope.spawn(|| (0..50).for_each(|_| assert!(sucker.history(1).is_ok())));
+            scope.spawn(|| {
+                for _ in 0..50 {
+                    let changed = sucker.get_if_changed(crate::Generation::default());
+                    assert_eq!(changed.unwrap().map(|(_, value)| value), Some(42));
+                }
+            });
+            // Responses to requests given up on go to nobody
+            scope.spawn(|| {
+                for _ in 0..50 {
+                    let _ = sucker.get_timeout(Duration::from_micros(500));
+                }
+            });
+        });
+        assert_eq!(sucker.get(), Ok(42));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_shared_source() {
+        use std::sync::Arc;
+
+        // Neither pair() nor set_shared needs the value to be Clone
+        #[derive(Debug, PartialEq)]
+        struct Snapshot(Vec<u8>);
+
+        let (sucker, sourcer) = StdSuck::<Snapshot>::pair();
+        let snapshot = Arc::new(Snapshot(vec![1, 2, 3]));
+        sourcer.set_shared(Arc::clone(&snapshot)).unwrap();
+        let handle = sourcer.handle();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert!(Arc::ptr_eq(&sucker.get_arc().unwrap(), &snapshot));
+        assert_eq!(sucker.get(), Err(Error::SharedOnly));
+
+        // Other values are put behind a new pointer
+        handle.set(|| Snapshot(vec![4])).unwrap();
+        assert_eq!(*sucker.get_arc().unwrap(), Snapshot(vec![4]));
+        assert_eq!(sucker.get(), Ok(Snapshot(vec![4])));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_with() {
+        use std::sync::Arc;
+
+        let (sucker, sourcer) = StdSuck::<Vec<u8>>::pair();
+        sourcer.set_shared(Arc::new(vec![1, 2, 3])).unwrap();
+        let handle = sourcer.handle();
+
+        // Fixed values are read without the producer
+        assert_eq!(sucker.with(|v| v.len()), Ok(3));
+        handle.set_static(vec![4, 5]).unwrap();
+        assert_eq!(sucker.with(|v| v[1]), Ok(5));
+
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+        handle.set(|| vec![6]).unwrap();
+        assert_eq!(sucker.with(|v| v.contains(&6)), Ok(true));
+        assert_eq!(
+            sucker.try_with(|v| v.get(1).copied().ok_or("too short")),
+            Err(Error::MapFailed("too short".to_string()))
+        );
+
+        sucker.close().unwrap();
+        assert_eq!(sucker.with(|v| v.len()), Err(Error::ChannelClosed));
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_update_and_compare_and_set() {
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        sourcer.set_static(0).unwrap();
+        let handle = sourcer.handle();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        // No increment is lost to a concurrent one
+        let updaters: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = handle.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        handle.update(|n| n + 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for updater in updaters {
+            updater.join().unwrap();
+        }
+        assert_eq!(sucker.get(), Ok(400));
+
+        let generation = handle.generation();
+        let next = handle.compare_and_set(generation, 7).unwrap();
+        assert_eq!(handle.compare_and_set(generation, 8), Err(Error::Conflict));
+        assert_eq!(sucker.get_next(generation), Ok((next, 7)));
+
+        handle.set(|| 1).unwrap();
+        assert_eq!(handle.update(|n| n + 1), Err(Error::NotStatic));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_history() {
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let sourcer = sourcer.with_history(3);
+        let handle = sourcer.handle();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(sucker.history(3), Ok(vec![]));
+        for n in 0..5 {
+            handle.set_static(n).unwrap();
+            assert_eq!(sucker.get(), Ok(n));
+        }
+
+        // Only the last three are kept, oldest first
+        let history = sucker.history(10).unwrap();
+        let values: Vec<_> = history.iter().map(|served| served.value).collect();
+        assert_eq!(values, vec![2, 3, 4]);
+        assert!(
+            history.windows(2).all(|pair| {
+                pair[0].generation < pair[1].generation && pair[0].at <= pair[1].at
+            })
+        );
+        assert_eq!(sucker.history(1).unwrap()[0].value, 4);
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_set_iter() {
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        sourcer.set_iter(vec![1, 2, 3]).unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(sucker.get(), Ok(1));
+        assert_eq!(sucker.get(), Ok(2));
+        assert_eq!(sucker.get(), Ok(3));
+        assert_eq!(sucker.get(), Err(Error::ChannelClosed));
+        assert_eq!(sucker.get(), Err(Error::ChannelClosed));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_set_latest_from() {
+        use std::sync::mpsc;
+        use std::time::{Duration, Instant};
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let (tx, rx) = mpsc::channel();
+        tx.send(1).unwrap();
+        sourcer.set_latest_from_wait(rx).unwrap();
+        let handle = sourcer.handle();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+        assert_eq!(sucker.get(), Ok(1));
+
+        let until_latest = |expected| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while sucker.get() != Ok(expected) {
+                assert!(Instant::now() < deadline, "never saw {expected}");
+                thread::sleep(Duration::from_millis(1));
+            }
+        };
+        tx.send(2).unwrap();
+        tx.send(3).unwrap();
+        until_latest(3);
+
+        // Setting another source stops the draining
+        handle.set_static(10).unwrap();
+        tx.send(4).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(sucker.get(), Ok(10));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_set_lazy() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let (sucker, sourcer) = StdSuck::<usize>::pair();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&runs);
+        sourcer
+            .set_lazy(move || {
+                // The first attempt panics, the next one is kept
+                if counted.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("intentional panic in the initialiser");
+                }
+                42
+            })
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+
+        assert_eq!(sucker.get(), Err(Error::NoSource));
+        for _ in 0..3 {
+            assert_eq!(sucker.get(), Ok(42));
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        use crate::{LimitPolicy, RateLimit};
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let limit = RateLimit::PerInterval {
+            evaluations: 2,
+            interval: Duration::from_secs(60),
+        };
+        let sourcer = sourcer.with_rate_limit(limit, LimitPolicy::Fail);
+        let counter = std::sync::atomic::AtomicU32::new(0);
+        sourcer
+            .set(move || counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+            .unwrap();
+        let handle = sourcer.handle();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(sucker.get(), Ok(0));
+        assert_eq!(sucker.get(), Ok(1));
+        assert_eq!(sucker.get(), Err(Error::RateLimited));
+
+        // Fixed values aren't limited
+        handle.set_static(7).unwrap();
+        assert_eq!(sucker.get(), Ok(7));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit_serve_last() {
+        use crate::{LimitPolicy, RateLimit};
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let limit = RateLimit::MinSpacing(Duration::from_millis(50));
+        let sourcer = sourcer.with_rate_limit(limit, LimitPolicy::ServeLast);
+        let counter = std::sync::atomic::AtomicU32::new(0);
+        sourcer
+            .set(move || counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(sucker.get(), Ok(0));
+        assert_eq!(sucker.get(), Ok(0));
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(sucker.get(), Ok(1));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_evaluation_timeout() {
+        use crate::InFlight;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let sourcer = sourcer.with_evaluation_timeout(Duration::from_millis(50), InFlight::Reuse);
+        let counter = AtomicU32::new(0);
+        sourcer
+            .set(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                if n == 0 {
+                    thread::sleep(Duration::from_millis(200));
+                }
+                n
+            })
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(sucker.get(), Err(Error::SourceTimeout));
+        thread::sleep(Duration::from_millis(250));
+        // The hung evaluation finished meanwhile and is served now
+        assert_eq!(sucker.get(), Ok(0));
+        assert_eq!(sucker.get(), Ok(1));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_evaluation_timeout_restart() {
+        use crate::InFlight;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let sourcer = sourcer.with_evaluation_timeout(Duration::from_millis(50), InFlight::Restart);
+        let counter = AtomicU32::new(0);
+        sourcer
+            .set(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                if n == 0 {
+                    thread::sleep(Duration::from_millis(200));
+                }
+                n
+            })
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(sucker.get(), Err(Error::SourceTimeout));
+        assert_eq!(sucker.get(), Ok(1));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_evaluation_timeout_restart_waits_for_mut() {
+        use crate::InFlight;
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let sourcer = sourcer.with_evaluation_timeout(Duration::from_millis(50), InFlight::Restart);
+        let mut next = 0;
+        sourcer
+            .set_mut(move || {
+                if next == 0 {
+                    thread::sleep(Duration::from_millis(200));
+                }
+                next += 1;
+                next - 1
+            })
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        // An FnMut source can't run twice at once, so no evaluation is
+        // started behind the hung one
+        assert_eq!(sucker.get(), Err(Error::SourceTimeout));
+        assert_eq!(sucker.get(), Err(Error::SourceTimeout));
+        thread::sleep(Duration::from_millis(250));
+        assert_eq!(sucker.get(), Ok(0));
+        assert_eq!(sucker.get(), Ok(1));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_evaluation_timeout_reuses_thread() {
+        use crate::InFlight;
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<thread::ThreadId>::pair();
+        let sourcer = sourcer.with_evaluation_timeout(Duration::from_secs(5), InFlight::Reuse);
+        sourcer.set(|| thread::current().id()).unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        let first = sucker.get().unwrap();
+        assert_ne!(first, thread::current().id());
+        assert_eq!(sucker.get(), Ok(first));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_set_cancellable() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let (cancelled_tx, cancelled_rx) = mpsc::channel();
+        let mut first = true;
+        sourcer
+            .set_cancellable(move |token| {
+                if !std::mem::take(&mut first) {
+                    return Some(1);
+                }
+                while !token.is_cancelled() {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                cancelled_tx.send(()).unwrap();
+                None
+            })
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        assert_eq!(
+            sucker.get_timeout(Duration::from_millis(50)),
+            Err(Error::Timeout)
+        );
+        cancelled_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        // The response of the cancelled evaluation is discarded
+        assert_eq!(sucker.get(), Ok(1));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_cancel_ignores_other_waiters() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let sucker = sucker.with_default_timeout(Duration::from_secs(5));
+        let (cancelled_tx, cancelled_rx) = mpsc::channel();
+        sourcer
+            .set_cancellable(move |token| {
+                while !token.is_cancelled() {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                cancelled_tx.send(()).unwrap();
+                None
+            })
+            .unwrap();
+        let handle = sourcer.handle();
+        let generation = sourcer.generation();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        thread::scope(|scope| {
+            // A long poll waits for another source, not for this value
+            let next = scope.spawn(|| sucker.get_next(generation));
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(
+                sucker.get_timeout(Duration::from_millis(50)),
+                Err(Error::Timeout)
+            );
+            cancelled_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+
+            handle.set_static(2).unwrap();
+            assert_eq!(next.join().unwrap().map(|(_, value)| value), Ok(2));
+        });
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_set_prefetch() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::{Duration, Instant};
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&runs);
+        sourcer
+            .set_prefetch(
+                move || match counted.fetch_add(1, Ordering::SeqCst) {
+                    0 => panic!("intentional panic in the prefetched closure"),
+                    n => n,
+                },
+                3,
+            )
+            .unwrap();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        // Values are computed before anyone pulls, but no more than `depth`
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while runs.load(Ordering::SeqCst) < 3 {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(1));
+        }
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        assert_eq!(sucker.get(), Err(Error::NoSource));
+        assert_eq!(sucker.get(), Ok(1));
+        assert_eq!(sucker.get(), Ok(2));
+        assert_eq!(sucker.get(), Ok(3));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_set_refreshing() {
+        use std::time::{Duration, Instant};
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let mut runs = 0;
+        let refresh = sourcer
+            .set_refreshing(
+                move || {
+                    runs += 1;
+                    // Every other run fails
+                    if runs % 2 == 0 {
+                        Err("flaky")
+                    } else {
+                        Ok(runs)
+                    }
+                },
+                Duration::from_millis(2),
+            )
+            .unwrap();
+        let handle = sourcer.handle();
+        let producer_handle = thread::spawn(move || sourcer.run().unwrap());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while refresh.failures() < 3 {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(1));
+        }
+        // Failed runs never replace the value
+        assert_eq!(sucker.get().map(|n| n % 2), Ok(1));
+
+        // Setting another source stops the refreshing
+        handle.set_static(100).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(sucker.get(), Ok(100));
+
+        sucker.close().unwrap();
+        producer_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_refreshing_stops_with_channel() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::Duration;
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&runs);
+        sourcer
+            .set_refreshing(
+                move || Ok::<_, &str>(counted.fetch_add(1, Ordering::SeqCst)),
+                Duration::from_millis(2),
+            )
+            .unwrap();
+
+        drop(sucker);
+        drop(sourcer);
+        thread::sleep(Duration::from_millis(20));
+        let stopped = runs.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(runs.load(Ordering::SeqCst), stopped);
+    }
+
+    #[test]
+    fn test_latest_from_stops_with_channel() {
+        use std::sync::mpsc;
+        use std::time::{Duration, Instant};
+
+        let (sucker, sourcer) = StdSuck::<u32>::pair();
+        let (tx, rx) = mpsc::channel();
+        sourcer.set_latest_from(rx).unwrap();
+
+        drop(sucker);
+        drop(sourcer);
+        // The next item finds the channel gone, so the receiver is dropped
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while tx.send(1).is_ok() {
+            assert!(Instant::now() < deadline, "still draining");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_send_timeout_wakes_on_room() {
+        let (tx, rx) = StdChannel::create_bounded_request_channel(1);
+        tx.send(types::Request::Close).unwrap();
+
+        let started = Instant::now();
+        thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(50));
+                rx.recv().unwrap();
+            });
+            tx.send_timeout(types::Request::Close, Duration::from_secs(5))
+                .unwrap();
+        });
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let started = Instant::now();
+        thread::scope(|s| {
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                drop(rx);
+            });
+            assert_eq!(
+                tx.send_timeout(types::Request::Close, Duration::from_secs(5)),
+                Err(ChannelError::ProducerDisconnected)
+            );
+        });
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
 }
@@ -1,11 +1,33 @@
+use std::time::Duration;
+
 pub use crate::error::Error as ChannelError;
 
 pub trait ChannelSender<T> {
     fn send(&self, msg: T) -> Result<(), ChannelError>;
+
+    /// Like [send](Self::send), but fails with [ChannelError::Busy] instead of
+    /// blocking when a bounded channel is full
+    fn try_send(&self, msg: T) -> Result<(), ChannelError>;
+
+    /// Like [send](Self::send), but fails with [ChannelError::Timeout] when a
+    /// bounded channel is still full once `timeout` has elapsed
+    fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), ChannelError>;
 }
 
 pub trait ChannelReceiver<T> {
     fn recv(&self) -> Result<T, ChannelError>;
+
+    /// Like [recv](Self::recv), but fails with [ChannelError::Timeout] once
+    /// `timeout` has elapsed
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError>;
+
+    /// Like [recv](Self::recv), but returns `None` instead of blocking when
+    /// the channel is empty
+    ///
+    /// Defaults to `None`, so backends that can't peek are taken to be empty.
+    fn try_recv(&self) -> Result<Option<T>, ChannelError> {
+        Ok(None)
+    }
 }
 
 pub trait ChannelType {
@@ -20,4 +42,47 @@ pub trait ChannelType {
         Self::Sender<crate::types::Response<T>>,
         Self::Receiver<crate::types::Response<T>>,
     );
+
+    /// A request channel holding at most `capacity` requests, which is at
+    /// least 1
+    fn create_bounded_request_channel(
+        capacity: usize,
+    ) -> (
+        Self::Sender<crate::types::Request>,
+        Self::Receiver<crate::types::Request>,
+    );
+}
+
+impl<T, S> ChannelSender<T> for Box<S>
+where
+    S: ChannelSender<T> + ?Sized,
+{
+    fn send(&self, msg: T) -> Result<(), ChannelError> {
+        (**self).send(msg)
+    }
+
+    fn try_send(&self, msg: T) -> Result<(), ChannelError> {
+        (**self).try_send(msg)
+    }
+
+    fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), ChannelError> {
+        (**self).send_timeout(msg, timeout)
+    }
+}
+
+impl<T, R> ChannelReceiver<T> for Box<R>
+where
+    R: ChannelReceiver<T> + ?Sized,
+{
+    fn recv(&self) -> Result<T, ChannelError> {
+        (**self).recv()
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError> {
+        (**self).recv_timeout(timeout)
+    }
+
+    fn try_recv(&self) -> Result<Option<T>, ChannelError> {
+        (**self).try_recv()
+    }
 }
@@ -0,0 +1,660 @@
+//! Conformance checks for channel backends
+//!
+//! Custom [ChannelType](crate::sync::traits::ChannelType)s and
+//! [AsyncChannelType](crate::asynchronous::traits::AsyncChannelType)s can run
+//! the same checks as the bundled backends from their own test modules:
+//!
+//! ```ignore
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//!
+//!     suck::testing::backend_conformance!(MyChannel);
+//!     suck::testing::async_backend_conformance!(MyAsyncChannel);
+//! }
+//! ```
+//!
+//! Each check is also exposed as a plain function, generic over the backend.
+//! Checks fail rather than hang when a backend never lets them finish.
+
+#[cfg(feature = "sync")]
+pub use sync::*;
+
+#[cfg(feature = "async-tokio")]
+pub use asynchronous::*;
+
+#[cfg(feature = "sync")]
+mod sync {
+    use std::panic;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::sync::channel::{SourcerFor, SuckerFor, pair, pair_bounded};
+    use crate::sync::traits::ChannelType;
+    use crate::types::Request;
+    use crate::{Error, OverflowPolicy};
+
+    /// How long a check may take before the backend is taken to hang
+    const CHECK_LIMIT: Duration = Duration::from_secs(10);
+
+    /// Run `check` on its own thread, failing instead of hanging the test
+    /// binary if the backend never lets it finish
+    fn within(check: impl FnOnce() + Send + 'static) {
+        let (done_tx, done_rx) = mpsc::channel();
+        let runner = thread::spawn(move || {
+            check();
+            let _ = done_tx.send(());
+        });
+        match done_rx.recv_timeout(CHECK_LIMIT) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                if let Err(panic) = runner.join() {
+                    panic::resume_unwind(panic);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                panic!("check still running after {CHECK_LIMIT:?}, the backend hangs")
+            }
+        }
+    }
+
+    /// Run the producer on its own thread after `setup` has installed a source
+    fn spawn_producer<C>(
+        sourcer: SourcerFor<C, i32>,
+        setup: impl FnOnce(&SourcerFor<C, i32>) + Send + 'static,
+    ) -> thread::JoinHandle<()>
+    where
+        C: ChannelType,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        thread::spawn(move || {
+            setup(&sourcer);
+            sourcer.run().unwrap();
+        })
+    }
+
+    /// A static value is handed out on every pull
+    pub fn static_value<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| s.set_static(42).unwrap());
+
+            assert_eq!(sucker.get(), Ok(42));
+            assert_eq!(sucker.get(), Ok(42));
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// A [Fn] source is evaluated once per pull
+    pub fn closure_value<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| {
+                let counter = std::sync::atomic::AtomicI32::new(0);
+                s.set(move || counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1)
+                    .unwrap();
+            });
+
+            assert_eq!(sucker.get(), Ok(1));
+            assert_eq!(sucker.get(), Ok(2));
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// A [FnMut] source keeps its state between pulls
+    pub fn mut_closure_value<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| {
+                let mut count = 0;
+                s.set_mut(move || {
+                    count += 1;
+                    count
+                })
+                .unwrap();
+            });
+
+            assert_eq!(sucker.get(), Ok(1));
+            assert_eq!(sucker.get(), Ok(2));
+            assert_eq!(sucker.get(), Ok(3));
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// Pulling before a source is set reports [Error::NoSource]
+    pub fn no_source<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |_| {});
+
+            assert_eq!(sucker.get(), Err(Error::NoSource));
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// Pulling after the consumer closed reports [Error::ChannelClosed]
+    pub fn closed_by_sucker<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| s.set_static(42).unwrap());
+
+            sucker.close().unwrap();
+            assert_eq!(sucker.get(), Err(Error::ChannelClosed));
+
+            producer.join().unwrap();
+        });
+    }
+
+    /// Pulling from a cleared source reports [Error::ChannelClosed]
+    pub fn closed_by_sourcer<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| {
+                s.set_static(42).unwrap();
+                s.close().unwrap();
+            });
+
+            assert_eq!(sucker.get(), Err(Error::ChannelClosed));
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// Pulls time out while nobody answers, and late responses are discarded
+    pub fn timeout<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+
+            assert_eq!(
+                sucker.get_timeout(Duration::from_millis(10)),
+                Err(Error::Timeout)
+            );
+
+            let mut count = 0;
+            sourcer
+                .set_mut(move || {
+                    count += 1;
+                    count
+                })
+                .unwrap();
+            let producer = spawn_producer::<C>(sourcer, |_| {});
+
+            assert_eq!(sucker.get(), Ok(2));
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// A full bounded queue fails with [Error::Busy] under [OverflowPolicy::Fail]
+    pub fn bounded_fail<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+    {
+        within(|| {
+            let (sucker, _sourcer) = pair_bounded::<C, i32>(1);
+            let sucker = sucker.with_overflow(OverflowPolicy::Fail);
+
+            // Nobody answers, so the request stays queued
+            assert_eq!(
+                sucker.get_timeout(Duration::from_millis(10)),
+                Err(Error::Timeout)
+            );
+            assert_eq!(sucker.get(), Err(Error::Busy));
+        });
+    }
+
+    /// A full bounded queue holds up pulls under [OverflowPolicy::Block] no
+    /// longer than their timeout
+    pub fn bounded_block<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+    {
+        within(|| {
+            let (sucker, _sourcer) = pair_bounded::<C, i32>(1);
+
+            // Nobody answers, so the request stays queued and the next can't join it
+            assert_eq!(
+                sucker.get_timeout(Duration::from_millis(10)),
+                Err(Error::Timeout)
+            );
+            assert_eq!(
+                sucker.get_timeout(Duration::from_millis(10)),
+                Err(Error::Timeout)
+            );
+        });
+    }
+
+    /// Pulls finding a full bounded queue under [OverflowPolicy::Coalesce]
+    /// share the producer's next evaluation
+    pub fn bounded_coalesce<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SuckerFor<C, i32>: Sync,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair_bounded::<C, i32>(1);
+            let sucker = sucker.with_overflow(OverflowPolicy::Coalesce);
+            let sourcer = sourcer.with_coalescing();
+
+            assert_eq!(
+                sucker.get_timeout(Duration::from_millis(10)),
+                Err(Error::Timeout)
+            );
+
+            // Only start answering once the next pulls have found the queue full
+            let producer = spawn_producer::<C>(sourcer, |s| {
+                thread::sleep(Duration::from_millis(100));
+                let counter = std::sync::atomic::AtomicI32::new(0);
+                s.set(move || counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1)
+                    .unwrap();
+            });
+
+            thread::scope(|scope| {
+                let pulls: Vec<_> = (0..3).map(|_| scope.spawn(|| sucker.get())).collect();
+                for pull in pulls {
+                    assert_eq!(pull.join().unwrap(), Ok(1));
+                }
+            });
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// A queue of capacity 0 holds one request, and late responses don't
+    /// hold up the producer
+    pub fn bounded_zero<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair_bounded::<C, i32>(0);
+            let producer = spawn_producer::<C>(sourcer, |s| {
+                s.set(|| {
+                    thread::sleep(Duration::from_millis(50));
+                    42
+                })
+                .unwrap()
+            });
+
+            assert_eq!(
+                sucker.get_timeout(Duration::from_millis(10)),
+                Err(Error::Timeout)
+            );
+            assert_eq!(sucker.get_timeout(Duration::from_millis(500)), Ok(42));
+
+            sucker.close().unwrap();
+            producer.join().unwrap();
+        });
+    }
+
+    /// Dropping the producer reports [Error::ProducerDisconnected]
+    pub fn producer_disconnected<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            drop(sourcer);
+
+            assert_eq!(sucker.get(), Err(Error::ProducerDisconnected));
+        });
+    }
+
+    /// Dropping the consumer stops the producer loop
+    pub fn sucker_dropped<C>()
+    where
+        C: ChannelType + 'static,
+        C::Sender<Request>: Send + Sync + 'static,
+        SourcerFor<C, i32>: Send + 'static,
+    {
+        within(|| {
+            let (sucker, sourcer) = pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| s.set_static(42).unwrap());
+
+            drop(sucker);
+            producer.join().unwrap();
+        });
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+mod asynchronous {
+    use std::future::Future;
+    use std::time::Duration;
+
+    use crate::Error;
+    use crate::asynchronous::channel::{
+        AsyncSourcerFor, AsyncSuckerFor, async_pair, async_pair_bounded,
+    };
+    use crate::asynchronous::traits::AsyncChannelType;
+
+    /// Drive an async check to completion on a fresh Tokio runtime
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build Tokio runtime")
+            .block_on(future)
+    }
+
+    /// How long a check may take before the backend is taken to hang
+    const CHECK_LIMIT: Duration = Duration::from_secs(10);
+
+    /// Run `check`, failing instead of hanging the test binary if the backend
+    /// never lets it finish
+    async fn within(check: impl Future<Output = ()>) {
+        if tokio::time::timeout(CHECK_LIMIT, check).await.is_err() {
+            panic!("check still running after {CHECK_LIMIT:?}, the backend hangs");
+        }
+    }
+
+    /// Run the producer as a task after `setup` has installed a source
+    fn spawn_producer<C>(
+        sourcer: AsyncSourcerFor<C, i32>,
+        setup: impl FnOnce(&AsyncSourcerFor<C, i32>),
+    ) -> tokio::task::JoinHandle<()>
+    where
+        C: AsyncChannelType,
+        AsyncSourcerFor<C, i32>: Send + 'static,
+    {
+        setup(&sourcer);
+        tokio::spawn(async move { sourcer.run().await.unwrap() })
+    }
+
+    /// A static value is handed out on every pull
+    pub async fn async_static_value<C>()
+    where
+        C: AsyncChannelType,
+        AsyncSourcerFor<C, i32>: Send + 'static,
+        AsyncSuckerFor<C, i32>: Send,
+    {
+        within(async {
+            let (sucker, sourcer) = async_pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| s.set_static(42).unwrap());
+
+            assert_eq!(sucker.get().await, Ok(42));
+            assert_eq!(sucker.get().await, Ok(42));
+
+            sucker.close().await.unwrap();
+            producer.await.unwrap();
+        })
+        .await
+    }
+
+    /// A [FnMut] source keeps its state between pulls
+    pub async fn async_mut_closure_value<C>()
+    where
+        C: AsyncChannelType,
+        AsyncSourcerFor<C, i32>: Send + 'static,
+        AsyncSuckerFor<C, i32>: Send,
+    {
+        within(async {
+            let (sucker, sourcer) = async_pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| {
+                let mut count = 0;
+                s.set_mut(move || {
+                    count += 1;
+                    count
+                })
+                .unwrap();
+            });
+
+            assert_eq!(sucker.get().await, Ok(1));
+            assert_eq!(sucker.get().await, Ok(2));
+
+            sucker.close().await.unwrap();
+            producer.await.unwrap();
+        })
+        .await
+    }
+
+    /// Pulling before a source is set reports [Error::NoSource]
+    pub async fn async_no_source<C>()
+    where
+        C: AsyncChannelType,
+        AsyncSourcerFor<C, i32>: Send + 'static,
+        AsyncSuckerFor<C, i32>: Send,
+    {
+        within(async {
+            let (sucker, sourcer) = async_pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |_| {});
+
+            assert_eq!(sucker.get().await, Err(Error::NoSource));
+
+            sucker.close().await.unwrap();
+            producer.await.unwrap();
+        })
+        .await
+    }
+
+    /// Pulling after the consumer closed reports [Error::ChannelClosed]
+    pub async fn async_closed_by_sucker<C>()
+    where
+        C: AsyncChannelType,
+        AsyncSourcerFor<C, i32>: Send + 'static,
+        AsyncSuckerFor<C, i32>: Send,
+    {
+        within(async {
+            let (sucker, sourcer) = async_pair::<C, i32>();
+            let producer = spawn_producer::<C>(sourcer, |s| s.set_static(42).unwrap());
+
+            sucker.close().await.unwrap();
+            assert_eq!(sucker.get().await, Err(Error::ChannelClosed));
+
+            producer.await.unwrap();
+        })
+        .await
+    }
+
+    /// A full bounded queue holds up pulls no longer than their timeout
+    pub async fn async_bounded_block<C>()
+    where
+        C: AsyncChannelType,
+    {
+        within(async {
+            let (sucker, _sourcer) = async_pair_bounded::<C, i32>(1);
+
+            // Nobody answers, so the request stays queued and the next can't join it
+            let timeout = Duration::from_millis(10);
+            assert_eq!(sucker.get_timeout(timeout).await, Err(Error::Timeout));
+            assert_eq!(sucker.get_timeout(timeout).await, Err(Error::Timeout));
+        })
+        .await
+    }
+
+    /// Dropping the producer reports [Error::ProducerDisconnected]
+    pub async fn async_producer_disconnected<C>()
+    where
+        C: AsyncChannelType,
+    {
+        within(async {
+            let (sucker, sourcer) = async_pair::<C, i32>();
+            drop(sourcer);
+
+            assert_eq!(sucker.get().await, Err(Error::ProducerDisconnected));
+        })
+        .await
+    }
+}
+
+/// Generate a `#[test]` per synchronous conformance check for a backend
+#[cfg(feature = "sync")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __backend_conformance {
+    ($channel:ty) => {
+        #[allow(unused_imports)]
+        mod backend_conformance {
+            use super::*;
+
+            #[test]
+            fn static_value() {
+                $crate::testing::static_value::<$channel>();
+            }
+
+            #[test]
+            fn closure_value() {
+                $crate::testing::closure_value::<$channel>();
+            }
+
+            #[test]
+            fn mut_closure_value() {
+                $crate::testing::mut_closure_value::<$channel>();
+            }
+
+            #[test]
+            fn no_source() {
+                $crate::testing::no_source::<$channel>();
+            }
+
+            #[test]
+            fn closed_by_sucker() {
+                $crate::testing::closed_by_sucker::<$channel>();
+            }
+
+            #[test]
+            fn closed_by_sourcer() {
+                $crate::testing::closed_by_sourcer::<$channel>();
+            }
+
+            #[test]
+            fn timeout() {
+                $crate::testing::timeout::<$channel>();
+            }
+
+            #[test]
+            fn bounded_fail() {
+                $crate::testing::bounded_fail::<$channel>();
+            }
+
+            #[test]
+            fn bounded_block() {
+                $crate::testing::bounded_block::<$channel>();
+            }
+
+            #[test]
+            fn bounded_coalesce() {
+                $crate::testing::bounded_coalesce::<$channel>();
+            }
+
+            #[test]
+            fn bounded_zero() {
+                $crate::testing::bounded_zero::<$channel>();
+            }
+
+            #[test]
+            fn producer_disconnected() {
+                $crate::testing::producer_disconnected::<$channel>();
+            }
+
+            #[test]
+            fn sucker_dropped() {
+                $crate::testing::sucker_dropped::<$channel>();
+            }
+        }
+    };
+}
+
+/// Generate a `#[test]` per asynchronous conformance check for a backend
+#[cfg(feature = "async-tokio")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __async_backend_conformance {
+    ($channel:ty) => {
+        #[allow(unused_imports)]
+        mod async_backend_conformance {
+            use super::*;
+
+            #[test]
+            fn static_value() {
+                $crate::testing::block_on($crate::testing::async_static_value::<$channel>());
+            }
+
+            #[test]
+            fn mut_closure_value() {
+                $crate::testing::block_on($crate::testing::async_mut_closure_value::<$channel>());
+            }
+
+            #[test]
+            fn no_source() {
+                $crate::testing::block_on($crate::testing::async_no_source::<$channel>());
+            }
+
+            #[test]
+            fn closed_by_sucker() {
+                $crate::testing::block_on($crate::testing::async_closed_by_sucker::<$channel>());
+            }
+
+            #[test]
+            fn bounded_block() {
+                $crate::testing::block_on($crate::testing::async_bounded_block::<$channel>());
+            }
+
+            #[test]
+            fn producer_disconnected() {
+                $crate::testing::block_on(
+                    $crate::testing::async_producer_disconnected::<$channel>(),
+                );
+            }
+        }
+    };
+}
+
+#[cfg(feature = "sync")]
+pub use crate::__backend_conformance as backend_conformance;
+
+#[cfg(feature = "async-tokio")]
+pub use crate::__async_backend_conformance as async_backend_conformance;
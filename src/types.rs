@@ -1,28 +1,1313 @@
-use std::sync::Mutex;
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError, mpsc};
+#[cfg(feature = "async")]
+use std::task::Poll;
+use std::task::Waker;
+use std::time::{Duration, Instant, SystemTime};
 
 use arc_swap::ArcSwap;
+#[cfg(feature = "async")]
+use futures_core::Stream;
+
+use crate::error::Error;
+use crate::handle::SourceHandle;
+
+/// Identifies a request, so its response reaches the consumer that sent it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(pub(crate) u64);
 
 /// Request messages sent from consumer to producer
 pub enum Request {
-    GetValue,
+    GetValue(RequestId),
+    /// Like [GetValue](Request::GetValue), but parked until a source is set
+    GetValueWait(RequestId),
+    /// Parked until a source newer than the generation is set, and answered
+    /// with the value along with its generation
+    GetNext(RequestId, Generation),
+    /// Like [GetValue](Request::GetValue), but answered with the shared
+    /// `Arc` of a source set by `set_shared`
+    GetArc(RequestId),
+    /// Answered with no value if the source is still that of the generation
+    /// and always produces the same value
+    GetIfChanged(RequestId, Generation),
+    /// Answered with up to that many of the last values served
+    History(RequestId, usize),
     Close,
+    /// Wake the producer to answer requests coalesced while the queue was
+    /// full, or parked requests given up on or ready since the source changed
+    Wake,
 }
 
 /// Response messages sent from producer to consumer
-pub enum Response<T> {
+pub struct Response<T> {
+    /// The request answered
+    pub(crate) id: RequestId,
+    pub(crate) reply: Reply<T>,
+}
+
+/// What a request is answered with
+pub(crate) enum Reply<T> {
     Value(T),
     NoSource,
     Closed,
+    /// The source failed to produce a value
+    Failed(Error),
+    /// A value along with the generation of the source that produced it
+    Versioned(Generation, T),
+    /// The source hasn't changed since the generation asked about
+    NotModified,
+    /// The `Arc<T>` of a source set by `set_shared`, with its type erased so the
+    /// reply can be sent whether or not `T` is [Sync]
+    Shared(SharedValue),
+    /// The last values served, oldest first
+    History(Vec<Served<T>>),
+}
+
+impl<T> Reply<T> {
+    /// Copy a reply to hand it to several waiting requests
+    pub(crate) fn share(&self, clone: fn(&T) -> T) -> Self {
+        match self {
+            Reply::Value(value) => Reply::Value(clone(value)),
+            Reply::NoSource => Reply::NoSource,
+            Reply::Closed => Reply::Closed,
+            Reply::Failed(error) => Reply::Failed(error.clone()),
+            Reply::Versioned(generation, value) => Reply::Versioned(*generation, clone(value)),
+            Reply::NotModified => Reply::NotModified,
+            Reply::Shared(value) => Reply::Shared(Arc::clone(value)),
+            Reply::History(served) => {
+                Reply::History(served.iter().map(|served| served.copy(clone)).collect())
+            }
+        }
+    }
+
+    /// The value, or the error a consumer sees instead
+    pub(crate) fn into_result(self) -> Result<T, Error> {
+        match self {
+            Reply::Value(value) | Reply::Versioned(_, value) => Ok(value),
+            Reply::NoSource => Err(Error::NoSource),
+            Reply::Closed => Err(Error::ChannelClosed),
+            Reply::Failed(error) => Err(error),
+            Reply::NotModified | Reply::History(_) => Err(Error::InternalError),
+            Reply::Shared(_) => Err(Error::SharedOnly),
+        }
+    }
+
+    /// Tag a value with the generation of the source that produced it
+    pub(crate) fn versioned(self, generation: Generation) -> Self {
+        match self {
+            Reply::Value(value) => Reply::Versioned(generation, value),
+            response => response,
+        }
+    }
+}
+
+/// Counts the sources set on a channel, starting from the default before the
+/// first one, see `Sucker::get_next`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Generation(pub(crate) u64);
+
+impl Generation {
+    pub(crate) fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+
+/// A value the producer served, as kept by `Sourcer::with_history`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Served<T> {
+    pub value: T,
+    /// The generation of the source that produced it
+    pub generation: Generation,
+    /// When it was produced
+    pub at: SystemTime,
+}
+
+impl<T> Served<T> {
+    fn copy(&self, clone: fn(&T) -> T) -> Self {
+        Self {
+            value: clone(&self.value),
+            generation: self.generation,
+            at: self.at,
+        }
+    }
+}
+
+/// Reports on the background runs of a source set by `set_refreshing`
+#[derive(Debug, Clone, Default)]
+pub struct Refresh {
+    failures: Arc<AtomicUsize>,
+}
+
+impl Refresh {
+    /// How many runs failed or panicked, keeping the value before them
+    pub fn failures(&self) -> usize {
+        self.failures.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn failed(&self) {
+        self.failures.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// A consumer counted by [Waiters::arrive] while its request is being sent,
+/// so one that fails or is dropped while waiting for room no longer counts
+pub(crate) struct Arrival<'a> {
+    waiters: &'a Waiters,
+    id: RequestId,
+    sent: bool,
+}
+
+impl Arrival<'_> {
+    /// The request is on its way, so the consumer waits for the response
+    pub(crate) fn sent(mut self) {
+        self.sent = true;
+    }
+}
+
+impl Drop for Arrival<'_> {
+    fn drop(&mut self) {
+        if !self.sent {
+            self.waiters.left(self.id);
+        }
+    }
+}
+
+/// The evaluation under way, answering [Waiters::answering]'s requests
+/// until dropped
+pub(crate) struct Answering<'a> {
+    waiters: &'a Waiters,
+}
+
+impl Drop for Answering<'_> {
+    fn drop(&mut self) {
+        self.waiters.lock().answering.clear();
+    }
+}
+
+/// Tracks the requests whose consumer still waits for a response, so
+/// evaluations nobody will read can be cancelled
+#[derive(Debug, Default)]
+pub(crate) struct Waiters {
+    state: Mutex<WaitersState>,
+    waker: Mutex<Option<Waker>>,
+}
+
+#[derive(Debug, Default)]
+struct WaitersState {
+    waiting: HashSet<RequestId>,
+    /// The requests the evaluation under way answers
+    answering: Vec<RequestId>,
+}
+
+impl WaitersState {
+    fn is_abandoned(&self) -> bool {
+        !self.answering.iter().any(|id| self.waiting.contains(id))
+    }
+}
+
+impl Waiters {
+    fn lock(&self) -> MutexGuard<'_, WaitersState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A consumer is about to send request `id`, and stops waiting when the
+    /// guard is dropped unless the request was [sent](Arrival::sent)
+    pub(crate) fn arrive(&self, id: RequestId) -> Arrival<'_> {
+        self.lock().waiting.insert(id);
+        Arrival {
+            waiters: self,
+            id,
+            sent: false,
+        }
+    }
+
+    /// The consumer of request `id` received its response, or gave up on it
+    pub(crate) fn left(&self, id: RequestId) {
+        let abandoned = {
+            let mut state = self.lock();
+            state.waiting.remove(&id) && state.answering.contains(&id) && state.is_abandoned()
+        };
+        if abandoned {
+            let waker = self
+                .waker
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+
+    /// The producer starts an evaluation answering `ids`
+    pub(crate) fn answering(&self, ids: &[RequestId]) -> Answering<'_> {
+        let mut state = self.lock();
+        state.answering.clear();
+        state.answering.extend_from_slice(ids);
+        Answering { waiters: self }
+    }
+
+    /// Whether no consumer waits for the evaluation under way, if any
+    pub(crate) fn is_abandoned(&self) -> bool {
+        self.lock().is_abandoned()
+    }
+
+    /// Resolves once no consumer waits for the evaluation under way
+    #[cfg(feature = "async")]
+    pub(crate) async fn abandoned(&self) {
+        std::future::poll_fn(|cx| {
+            *self.waker.lock().unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+            match self.is_abandoned() {
+                true => Poll::Ready(()),
+                false => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+/// Tells a source set by `set_cancellable` whether anybody still waits for
+/// the value it is computing
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    waiters: Arc<Waiters>,
+    cancelled: Arc<AtomicBool>,
 }
 
+impl CancellationToken {
+    pub(crate) fn new(waiters: Arc<Waiters>) -> Self {
+        Self {
+            waiters,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether every consumer waiting for this value gave up on it, so the
+    /// source may stop and return `None`
+    ///
+    /// Once cancelled, a token stays cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Acquire) {
+            return true;
+        }
+        let cancelled = self.waiters.is_abandoned();
+        if cancelled {
+            self.cancelled.store(true, Ordering::Release);
+        }
+        cancelled
+    }
+}
+
+/// An `Arc<T>` with its type erased
+pub(crate) type SharedValue = Arc<dyn Any + Send + Sync>;
+
+/// A closure source implementing [Fn]
+pub(crate) type SourceFn<T> = Box<dyn Fn() -> Result<T, Error> + Send + Sync + 'static>;
+/// A closure source implementing [FnMut]
+pub(crate) type SourceFnMut<T> = Box<dyn FnMut() -> Result<T, Error> + Send + 'static>;
+/// A stream source, polled by the async producer
+#[cfg(feature = "async")]
+pub(crate) type SourceStream<T> = Pin<Box<dyn Stream<Item = T> + Send + 'static>>;
+
 /// Represents the source of values: either static or dynamic
 pub(crate) enum ValueSource<T> {
-    Static { val: T, clone: fn(&T) -> T },
-    DynamicMut(Mutex<Box<dyn FnMut() -> T + Send + Sync + 'static>>),
-    Dynamic(Box<dyn Fn() -> T + Send + Sync + 'static>),
+    Static {
+        val: T,
+        clone: fn(&T) -> T,
+    },
+    /// A fixed `Arc<T>`, handed out without copying the value
+    Shared(SharedValue),
+    DynamicMut(Mutex<SourceFnMut<T>>),
+    /// Evaluated until it first succeeds, then replaced by a
+    /// [Static](ValueSource::Static) of its value
+    Lazy {
+        init: Mutex<SourceFnMut<T>>,
+        clone: fn(&T) -> T,
+    },
+    Dynamic(SourceFn<T>),
+    #[cfg(feature = "async")]
+    Stream(Mutex<SourceStream<T>>),
     None,    // Never set
     Cleared, // Was set but cleared (closed)
 }
 
+/// The outcome of evaluating a source once
+pub(crate) enum Evaluation<T> {
+    Value(T),
+    /// The value of a [ValueSource::Shared]
+    Shared(SharedValue),
+    /// The source returned an error, or panicked if there is none
+    Failed(Option<Error>),
+    NoSource,
+    Closed,
+    /// Nobody waited for the value anymore
+    Cancelled,
+}
+
+impl<T> ValueSource<T> {
+    pub(crate) fn dynamic<F>(closure: F) -> Self
+    where
+        F: Fn() -> Result<T, Error> + Send + Sync + 'static,
+    {
+        ValueSource::Dynamic(Box::new(closure))
+    }
+
+    pub(crate) fn dynamic_mut<F>(closure: F) -> Self
+    where
+        F: FnMut() -> Result<T, Error> + Send + 'static,
+    {
+        ValueSource::DynamicMut(Mutex::new(Box::new(closure)))
+    }
+
+    /// Take one element per evaluation, closing the channel once `iter` is
+    /// exhausted
+    pub(crate) fn iter<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = T> + Send + 'static,
+    {
+        let mut iter = iter;
+        Self::dynamic_mut(move || iter.next().ok_or(Error::ChannelClosed))
+    }
+
+    /// Whether every evaluation produces the same value, so an unchanged
+    /// source needn't be sent again
+    pub(crate) fn is_fixed(&self) -> bool {
+        matches!(self, ValueSource::Static { .. } | ValueSource::Shared(_))
+    }
+
+    /// Whether evaluations of the source wait for one another, as an [FnMut]
+    /// closure can't run twice at once
+    pub(crate) fn is_exclusive(&self) -> bool {
+        matches!(self, ValueSource::DynamicMut(_) | ValueSource::Lazy { .. })
+    }
+
+    /// Whether the source runs caller code on every evaluation, and so is
+    /// subject to a [RateLimit]
+    pub(crate) fn is_evaluated(&self) -> bool {
+        !matches!(
+            self,
+            ValueSource::Static { .. }
+                | ValueSource::Shared(_)
+                | ValueSource::None
+                | ValueSource::Cleared
+        )
+    }
+
+    /// Produce one value, catching panics from the source
+    pub(crate) fn evaluate(&self, poison: PoisonPolicy) -> Evaluation<T> {
+        let result = match self {
+            ValueSource::Static { val, clone } => execute_closure_safely(&mut || Ok(clone(val))),
+            ValueSource::Dynamic(closure) => execute_closure_safely(&mut || closure()),
+            ValueSource::DynamicMut(closure) | ValueSource::Lazy { init: closure, .. } => {
+                let mut closure = match (closure.lock(), poison) {
+                    (Ok(closure), _) => closure,
+                    (Err(poisoned), PoisonPolicy::Recover) => {
+                        closure.clear_poison();
+                        poisoned.into_inner()
+                    }
+                    (Err(_), PoisonPolicy::Fail) => {
+                        return Evaluation::Failed(Some(Error::SourcePoisoned));
+                    }
+                };
+                execute_closure_safely(&mut *closure)
+            }
+            ValueSource::Shared(val) => return Evaluation::Shared(Arc::clone(val)),
+            // Only the async producer polls streams
+            #[cfg(feature = "async")]
+            ValueSource::Stream(_) => return Evaluation::Failed(Some(Error::InternalError)),
+            ValueSource::None => return Evaluation::NoSource, // No source was ever set
+            ValueSource::Cleared => return Evaluation::Closed, // Channel was closed (source was set then cleared)
+        };
+
+        match result {
+            Ok(Ok(value)) => Evaluation::Value(value),
+            // An exhausted iterator
+            Ok(Err(Error::ChannelClosed)) => Evaluation::Closed,
+            // A cancellable source that gave up
+            Ok(Err(Error::Cancelled)) => Evaluation::Cancelled,
+            Ok(Err(error)) => Evaluation::Failed(Some(error)),
+            Err(_) => Evaluation::Failed(None), // Closure execution panicked
+        }
+    }
+}
+
+/// Poll a stream source for its next element, catching panics from it
+///
+/// Unlike a closure's, the lock is taken inside the panic boundary, so a
+/// stream that panics poisons it.
+#[cfg(feature = "async")]
+pub(crate) async fn evaluate_stream<T>(
+    stream: &Mutex<SourceStream<T>>,
+    poison: PoisonPolicy,
+) -> Evaluation<T> {
+    std::future::poll_fn(|cx| {
+        let polled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut locked = match (stream.lock(), poison) {
+                (Ok(locked), _) => locked,
+                (Err(poisoned), PoisonPolicy::Recover) => {
+                    stream.clear_poison();
+                    poisoned.into_inner()
+                }
+                (Err(_), PoisonPolicy::Fail) => return Err(Error::SourcePoisoned),
+            };
+            Ok(locked.as_mut().poll_next(cx))
+        }));
+        match polled {
+            Ok(Ok(Poll::Ready(Some(value)))) => Poll::Ready(Evaluation::Value(value)),
+            Ok(Ok(Poll::Ready(None))) => Poll::Ready(Evaluation::Closed),
+            Ok(Ok(Poll::Pending)) => Poll::Pending,
+            Ok(Err(error)) => Poll::Ready(Evaluation::Failed(Some(error))),
+            Err(_) => Poll::Ready(Evaluation::Failed(None)),
+        }
+    })
+    .await
+}
+
+/// An [FnMut] source whose lock was poisoned by a panic, for tests
+#[cfg(all(test, any(feature = "sync-std", feature = "async-tokio")))]
+pub(crate) fn poisoned_mut<T, F>(closure: F) -> ValueSource<T>
+where
+    F: FnMut() -> T + Send + Sync + 'static,
+{
+    let mut closure = closure;
+    let source = ValueSource::dynamic_mut(move || Ok(closure()));
+    if let ValueSource::DynamicMut(mutex) = &source {
+        std::thread::scope(|scope| {
+            let poisoner = scope.spawn(|| {
+                let _guard = mutex.lock().unwrap();
+                panic!("intentional panic while holding the source lock");
+            });
+            assert!(poisoner.join().is_err());
+        });
+    }
+    source
+}
+
+fn execute_closure_safely<T>(
+    closure: &mut dyn FnMut() -> Result<T, Error>,
+) -> Result<Result<T, Error>, Box<dyn std::any::Any + Send>> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(closure))
+}
+
+/// When a pull giving up after `timeout` does so
+pub(crate) fn deadline_after(timeout: Option<Duration>) -> Option<Instant> {
+    timeout.map(|timeout| Instant::now() + timeout)
+}
+
+/// A request parked by the producer until the source changes
+#[derive(Clone, Copy)]
+pub(crate) enum ParkedRequest {
+    /// Waiting for a source to be set
+    Value,
+    /// Waiting for a source newer than the generation
+    Next(Generation),
+}
+
+/// Requests parked by the producer, oldest first
+#[derive(Default)]
+pub(crate) struct Parked {
+    requests: Vec<(RequestId, ParkedRequest)>,
+}
+
+impl Parked {
+    pub(crate) fn push(&mut self, id: RequestId, request: ParkedRequest) {
+        self.requests.push((id, request));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Unpark the requests that can be answered now
+    pub(crate) fn take_ready(
+        &mut self,
+        generation: Generation,
+        closed: bool,
+    ) -> Vec<(RequestId, ParkedRequest)> {
+        let (ready, parked) = self.requests.iter().partition(|(_, request)| {
+            closed
+                || match request {
+                    ParkedRequest::Value => generation > Generation::default(),
+                    ParkedRequest::Next(after) => generation > *after,
+                }
+        });
+        self.requests = parked;
+        ready
+    }
+
+    /// Unpark the request, if it is still parked
+    pub(crate) fn take(&mut self, id: RequestId) -> Option<ParkedRequest> {
+        let index = self.requests.iter().position(|(parked, _)| *parked == id)?;
+        Some(self.requests.remove(index).1)
+    }
+}
+
+/// Wakes the producer for parked requests, which don't wait in the queue
+///
+/// Rather than checking on the source now and then, the producer is woken
+/// when the source changes or a consumer gives up on a parked request.
+#[derive(Default)]
+pub(crate) struct Doorbell {
+    waiting: Mutex<Waiting>,
+    ring: OnceLock<Box<dyn Fn() + Send + Sync>>,
+}
+
+#[derive(Default)]
+struct Waiting {
+    /// Whether the producer has requests parked until the source changes
+    parked: bool,
+    /// Parked requests whose consumers gave up on them
+    unparked: Vec<RequestId>,
+}
+
+impl Doorbell {
+    /// Wake the producer with `ring` from now on
+    pub(crate) fn connect(&self, ring: impl Fn() + Send + Sync + 'static) {
+        let _ = self.ring.set(Box::new(ring));
+    }
+
+    /// Record whether requests are parked, before the producer checks on the
+    /// source for them, so a change made after the check wakes it up
+    pub(crate) fn park(&self, parked: bool) {
+        self.lock().parked = parked;
+    }
+
+    /// Wake the producer after the source changed, if requests are parked
+    pub(crate) fn changed(&self) {
+        let parked = self.lock().parked;
+        if parked {
+            self.ring();
+        }
+    }
+
+    /// Have the producer answer parked request `id` right away, as its
+    /// consumer gave up on it
+    ///
+    /// Unlike a request, this can't be turned away by a full queue: should the
+    /// wake-up not fit, the producer has requests to handle anyway.
+    pub(crate) fn unpark(&self, id: RequestId) {
+        self.lock().unparked.push(id);
+        self.ring();
+    }
+
+    /// The parked requests given up on since the last call
+    pub(crate) fn take_unparked(&self) -> Vec<RequestId> {
+        std::mem::take(&mut self.lock().unparked)
+    }
+
+    fn ring(&self) {
+        if let Some(ring) = self.ring.get() {
+            ring();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Waiting> {
+        self.waiting.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Hands each response to the consumer waiting for it, so consumers sharing a
+/// sucker never get one another's
+pub(crate) struct Mailbox<T> {
+    next: AtomicU64,
+    letters: Mutex<Letters<T>>,
+    delivered: Condvar,
+}
+
+struct Letters<T> {
+    /// Replies received by one consumer for another
+    ready: HashMap<RequestId, Reply<T>>,
+    /// Requests whose consumer gave up, so their replies are dropped
+    abandoned: HashSet<RequestId>,
+    /// Tasks to wake once their reply is ready
+    #[cfg(feature = "async")]
+    wakers: HashMap<RequestId, Waker>,
+    /// Whether a consumer is receiving for the others
+    #[cfg(feature = "sync")]
+    receiving: bool,
+}
+
+impl<T> Letters<T> {
+    /// Keep a response for the consumer it answers, unless it gave up
+    fn file(&mut self, response: Response<T>) {
+        if self.abandoned.remove(&response.id) {
+            return;
+        }
+        #[cfg(feature = "async")]
+        if let Some(waker) = self.wakers.remove(&response.id) {
+            waker.wake();
+        }
+        self.ready.insert(response.id, response.reply);
+    }
+}
+
+impl<T> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self {
+            next: AtomicU64::new(0),
+            letters: Mutex::new(Letters {
+                ready: HashMap::new(),
+                abandoned: HashSet::new(),
+                #[cfg(feature = "async")]
+                wakers: HashMap::new(),
+                #[cfg(feature = "sync")]
+                receiving: false,
+            }),
+            delivered: Condvar::new(),
+        }
+    }
+}
+
+impl<T> Mailbox<T> {
+    /// Allocate the id of a new request
+    pub(crate) fn next_id(&self) -> RequestId {
+        RequestId(self.next.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Letters<T>> {
+        self.letters.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keep a response received for another consumer until it collects it
+    #[cfg(feature = "async")]
+    pub(crate) fn file(&self, response: Response<T>) {
+        self.lock().file(response);
+        self.delivered.notify_all();
+    }
+
+    /// Take the reply to the request if it is ready, or wake the task once it is
+    #[cfg(feature = "async")]
+    pub(crate) fn collect(&self, id: RequestId, waker: &Waker) -> Option<Reply<T>> {
+        let mut letters = self.lock();
+        let reply = letters.ready.remove(&id);
+        match reply {
+            Some(_) => letters.wakers.remove(&id),
+            None => letters.wakers.insert(id, waker.clone()),
+        };
+        reply
+    }
+
+    /// Drop the reply to the request, now or once it arrives
+    pub(crate) fn abandon(&self, id: RequestId) {
+        let mut letters = self.lock();
+        #[cfg(feature = "async")]
+        letters.wakers.remove(&id);
+        if letters.ready.remove(&id).is_none() {
+            letters.abandoned.insert(id);
+        }
+    }
+
+    /// Wait until the deadline for the reply to the request, receiving with
+    /// `recv` while no other thread does and filing replies for the others
+    #[cfg(feature = "sync")]
+    pub(crate) fn recv<F>(
+        &self,
+        id: RequestId,
+        deadline: Option<Instant>,
+        recv: F,
+    ) -> Result<Reply<T>, Error>
+    where
+        F: Fn(Option<Duration>) -> Result<Response<T>, Error>,
+    {
+        let mut letters = self.lock();
+        loop {
+            if let Some(reply) = letters.ready.remove(&id) {
+                return Ok(reply);
+            }
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if letters.receiving {
+                letters = match remaining {
+                    Some(remaining) if remaining.is_zero() => {
+                        letters.abandoned.insert(id);
+                        return Err(Error::Timeout);
+                    }
+                    Some(remaining) => {
+                        let waited = self.delivered.wait_timeout(letters, remaining);
+                        waited.unwrap_or_else(PoisonError::into_inner).0
+                    }
+                    None => self
+                        .delivered
+                        .wait(letters)
+                        .unwrap_or_else(PoisonError::into_inner),
+                };
+                continue;
+            }
+            letters.receiving = true;
+            drop(letters);
+            let received = recv(remaining);
+            letters = self.lock();
+            letters.receiving = false;
+            // File the reply before the others look for theirs, so none starts
+            // receiving for a reply that already arrived
+            let received = match received {
+                Ok(response) if response.id == id => Ok(Some(response.reply)),
+                Ok(response) => {
+                    letters.file(response);
+                    Ok(None)
+                }
+                Err(error) => Err(error),
+            };
+            self.delivered.notify_all();
+            match received {
+                Ok(Some(reply)) => return Ok(reply),
+                Ok(None) => {}
+                Err(Error::Timeout) => {
+                    letters.abandoned.insert(id);
+                    return Err(Error::Timeout);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// The requests a pull is waiting on, so it receives or abandons their responses
+#[doc(hidden)]
+pub enum Ticket {
+    Request(RequestId),
+    /// Sent to a fallback, as the primary had failed
+    Diverted(Box<Ticket>),
+    /// One per sucker of a zip
+    Many(Vec<Ticket>),
+}
+
+/// The current source along with its generation, swapped as one so both
+/// always agree
+pub(crate) struct Slot<T> {
+    pub(crate) source: ValueSource<T>,
+    pub(crate) generation: Generation,
+}
+
 /// Internal channel state shared between producer and consumer
-pub(crate) type ChannelState<T> = ArcSwap<ValueSource<T>>;
+pub(crate) type ChannelState<T> = ArcSwap<Slot<T>>;
+
+/// What a consumer does when the bounded request queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Wait for room in the queue, for no longer than the pull's timeout
+    #[default]
+    Block,
+    /// Fail with [Error::Busy]
+    Fail,
+    /// Join the evaluation the producer is running or about to run, and be
+    /// answered from its result
+    ///
+    /// This takes a producer sharing its results, see `with_coalescing`;
+    /// without one, wait for room as under [Block](Self::Block).
+    Coalesce,
+}
+
+/// Pulls that found a full queue under [OverflowPolicy::Coalesce], waiting
+/// to share the producer's next evaluation
+#[derive(Debug, Default)]
+pub(crate) struct Coalesced {
+    ids: Mutex<Vec<RequestId>>,
+    shared: AtomicBool,
+}
+
+impl Coalesced {
+    /// Whether the producer answers every waiting request from one
+    /// evaluation, which pulls can then join
+    pub(crate) fn is_shared(&self) -> bool {
+        self.shared.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_shared(&self, shared: bool) {
+        self.shared.store(shared, Ordering::Release);
+    }
+
+    pub(crate) fn push(&self, id: RequestId) {
+        self.ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(id);
+    }
+
+    pub(crate) fn take(&self) -> Vec<RequestId> {
+        std::mem::take(&mut *self.ids.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// What the producer does when a source panics or returns an error
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FailurePolicy {
+    /// Report the failure: a panic as [Error::NoSource], an error from a
+    /// fallible source as [Error::SourceFailed]
+    #[default]
+    ReturnError,
+    /// Evaluate the source up to `attempts` more times, waiting `backoff`
+    /// before each, then report the failure
+    RetryN { attempts: usize, backoff: Duration },
+    /// Serve the last value the current source produced, if there is one
+    ServeLastGood,
+    /// Close the channel, so this and later pulls see [Error::ChannelClosed]
+    CloseChannel,
+}
+
+/// What the producer does when the lock around an [FnMut] source or a
+/// stream was poisoned by a panic
+///
+/// Panics from an [FnMut] closure are caught while its lock is held, so they
+/// don't poison it; for closures this only covers poisoning from outside the
+/// source. A stream that panics while being polled does poison its lock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PoisonPolicy {
+    /// Clear the poison and keep using the source
+    #[default]
+    Recover,
+    /// Fail with [Error::SourcePoisoned] until
+    /// a new source is set
+    Fail,
+}
+
+/// How often the producer may evaluate a closure source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimit {
+    /// At most `evaluations` within any `interval`
+    PerInterval {
+        evaluations: usize,
+        interval: Duration,
+    },
+    /// At least this long between evaluations
+    MinSpacing(Duration),
+}
+
+/// What a pull gets when evaluating the source would break its [RateLimit]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LimitPolicy {
+    /// Fail with [Error::RateLimited]
+    #[default]
+    Fail,
+    /// Serve the last value the source produced, failing as above if there
+    /// is none
+    ServeLast,
+}
+
+/// What a pull does about an evaluation still running when an earlier pull
+/// gave up on it, see `Sourcer::with_evaluation_timeout`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum InFlight {
+    /// Wait for that evaluation instead of starting another
+    #[default]
+    Reuse,
+    /// Abandon that evaluation and start afresh, unless the source is an
+    /// [FnMut] closure, which can't run twice at once and is waited for
+    Restart,
+}
+
+/// A helper thread evaluating sources, reused for as long as it keeps up
+#[cfg_attr(all(feature = "async-tokio", not(feature = "sync")), allow(dead_code))]
+struct Evaluator<T> {
+    jobs: mpsc::Sender<(Arc<Slot<T>>, PoisonPolicy)>,
+    done: mpsc::Receiver<Evaluation<T>>,
+}
+
+/// Starts evaluating a source on one of Tokio's blocking threads
+#[cfg(feature = "async-tokio")]
+type SpawnTask<T> = fn(Arc<Slot<T>>, PoisonPolicy) -> tokio::task::JoinHandle<Evaluation<T>>;
+
+/// Gives up on closure sources that take longer than a timeout
+#[cfg_attr(all(feature = "async-tokio", not(feature = "sync")), allow(dead_code))]
+pub(crate) struct Deadline<T> {
+    timeout: Option<Duration>,
+    policy: InFlight,
+    spawn: Option<fn() -> Evaluator<T>>,
+    /// The helper thread done with its last evaluation
+    idle: Mutex<Option<Evaluator<T>>>,
+    /// The helper thread still busy with an evaluation given up on
+    in_flight: Mutex<Option<(Generation, Evaluator<T>)>>,
+    #[cfg(feature = "async-tokio")]
+    spawn_task: Option<SpawnTask<T>>,
+    /// The blocking task still busy with an evaluation given up on
+    #[cfg(feature = "async-tokio")]
+    in_flight_task: Mutex<Option<(Generation, tokio::task::JoinHandle<Evaluation<T>>)>>,
+}
+
+impl<T> Default for Deadline<T> {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            policy: InFlight::default(),
+            spawn: None,
+            idle: Mutex::new(None),
+            in_flight: Mutex::new(None),
+            #[cfg(feature = "async-tokio")]
+            spawn_task: None,
+            #[cfg(feature = "async-tokio")]
+            in_flight_task: Mutex::new(None),
+        }
+    }
+}
+
+impl<T> Deadline<T> {
+    pub(crate) fn new(timeout: Duration, policy: InFlight) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        Self {
+            timeout: Some(timeout),
+            policy,
+            spawn: Some(spawn_evaluator),
+            idle: Mutex::new(None),
+            in_flight: Mutex::new(None),
+            #[cfg(feature = "async-tokio")]
+            spawn_task: Some(spawn_evaluation_task),
+            #[cfg(feature = "async-tokio")]
+            in_flight_task: Mutex::new(None),
+        }
+    }
+
+    /// Whether to wait for the evaluation of `slot` given up on before,
+    /// rather than start another
+    fn reuses(&self, generation: Generation, slot: &Slot<T>) -> bool {
+        generation == slot.generation
+            && (self.policy == InFlight::Reuse || slot.source.is_exclusive())
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Evaluate a closure source on a helper thread, failing with
+    /// [Error::SourceTimeout] if it takes too long; other sources are
+    /// evaluated right away
+    #[cfg_attr(all(feature = "async-tokio", not(feature = "sync")), allow(dead_code))]
+    pub(crate) fn evaluate(&self, slot: &Arc<Slot<T>>, poison: PoisonPolicy) -> Evaluation<T> {
+        let (Some(timeout), Some(spawn)) = (self.timeout, self.spawn) else {
+            return slot.source.evaluate(poison);
+        };
+        if !slot.source.is_evaluated() {
+            return slot.source.evaluate(poison);
+        }
+
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let evaluator = match in_flight.take() {
+            Some((generation, evaluator)) if self.reuses(generation, slot) => evaluator,
+            // Dropping an evaluator given up on lets its thread end once done
+            _ => {
+                let idle = self
+                    .idle
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .take();
+                let evaluator = idle.unwrap_or_else(spawn);
+                // Should the thread be gone, receiving fails below
+                let _ = evaluator.jobs.send((Arc::clone(slot), poison));
+                evaluator
+            }
+        };
+        match evaluator.done.recv_timeout(timeout) {
+            Ok(evaluation) => {
+                *self.idle.lock().unwrap_or_else(PoisonError::into_inner) = Some(evaluator);
+                evaluation
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                *in_flight = Some((slot.generation, evaluator));
+                Evaluation::Failed(Some(Error::SourceTimeout))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Evaluation::Failed(None),
+        }
+    }
+
+    /// Like [evaluate](Self::evaluate), on one of Tokio's blocking threads
+    /// and without blocking the producer's
+    #[cfg(feature = "async-tokio")]
+    pub(crate) async fn evaluate_async(
+        &self,
+        slot: &Arc<Slot<T>>,
+        poison: PoisonPolicy,
+    ) -> Evaluation<T> {
+        let (Some(timeout), Some(spawn)) = (self.timeout, self.spawn_task) else {
+            return slot.source.evaluate(poison);
+        };
+        if !slot.source.is_evaluated() {
+            return slot.source.evaluate(poison);
+        }
+
+        let in_flight = self
+            .in_flight_task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let mut task = match in_flight {
+            Some((generation, task)) if self.reuses(generation, slot) => task,
+            // Dropping a task given up on detaches it
+            _ => spawn(Arc::clone(slot), poison),
+        };
+        match tokio::time::timeout(timeout, &mut task).await {
+            Ok(Ok(evaluation)) => evaluation,
+            // Panics are caught by the evaluation, so the runtime is shutting down
+            Ok(Err(_)) => Evaluation::Failed(None),
+            Err(_) => {
+                *self
+                    .in_flight_task
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner) = Some((slot.generation, task));
+                Evaluation::Failed(Some(Error::SourceTimeout))
+            }
+        }
+    }
+}
+
+fn spawn_evaluator<T>() -> Evaluator<T>
+where
+    T: Send + Sync + 'static,
+{
+    let (jobs, queued) = mpsc::channel::<(Arc<Slot<T>>, PoisonPolicy)>();
+    let (finished, done) = mpsc::channel();
+    std::thread::spawn(move || {
+        for (slot, poison) in queued {
+            // Nobody is waiting any more if the evaluator was abandoned
+            if finished.send(slot.source.evaluate(poison)).is_err() {
+                break;
+            }
+        }
+    });
+    Evaluator { jobs, done }
+}
+
+#[cfg(feature = "async-tokio")]
+fn spawn_evaluation_task<T>(
+    slot: Arc<Slot<T>>,
+    poison: PoisonPolicy,
+) -> tokio::task::JoinHandle<Evaluation<T>>
+where
+    T: Send + Sync + 'static,
+{
+    tokio::task::spawn_blocking(move || slot.source.evaluate(poison))
+}
+
+/// Applies a [RateLimit], remembering what it needs to
+pub(crate) struct Limiter<T> {
+    limit: Option<RateLimit>,
+    policy: LimitPolicy,
+    clone: Option<fn(&T) -> T>,
+    evaluations: Mutex<VecDeque<Instant>>,
+    last: Mutex<Option<(Generation, T)>>,
+}
+
+impl<T> Default for Limiter<T> {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            policy: LimitPolicy::default(),
+            clone: None,
+            evaluations: Mutex::new(VecDeque::new()),
+            last: Mutex::new(None),
+        }
+    }
+}
+
+impl<T> Limiter<T> {
+    pub(crate) fn new(limit: RateLimit, policy: LimitPolicy, clone: fn(&T) -> T) -> Self {
+        Self {
+            limit: Some(limit),
+            policy,
+            clone: Some(clone),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the source may be evaluated now, counting the evaluation if so
+    pub(crate) fn admit(&self) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+
+        let now = Instant::now();
+        let mut evaluations = self
+            .evaluations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (evaluations_allowed, window) = match limit {
+            RateLimit::PerInterval {
+                evaluations,
+                interval,
+            } => (evaluations, interval),
+            RateLimit::MinSpacing(spacing) => (1, spacing),
+        };
+        while evaluations
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= window)
+        {
+            evaluations.pop_front();
+        }
+        if evaluations.len() >= evaluations_allowed {
+            return false;
+        }
+        evaluations.push_back(now);
+        true
+    }
+
+    /// Keep a copy of a value produced by the source of `generation`, if it
+    /// may be served later
+    pub(crate) fn succeeded(&self, value: &T, generation: Generation) {
+        if let (LimitPolicy::ServeLast, Some(clone)) = (self.policy, self.clone) {
+            *self.last.lock().unwrap_or_else(PoisonError::into_inner) =
+                Some((generation, clone(value)));
+        }
+    }
+
+    /// Build the response for a pull over the limit on the source of
+    /// `generation`
+    pub(crate) fn over_limit(&self, generation: Generation) -> Reply<T> {
+        let last = self.last.lock().unwrap_or_else(PoisonError::into_inner);
+        match (self.policy, self.clone, &*last) {
+            // Only a value of the current source will do
+            (LimitPolicy::ServeLast, Some(clone), Some((served, value)))
+                if *served == generation =>
+            {
+                Reply::Value(clone(value))
+            }
+            _ => Reply::Failed(Error::RateLimited),
+        }
+    }
+}
+
+/// Keeps the last values served, if asked to
+pub(crate) struct History<T> {
+    capacity: usize,
+    clone: Option<fn(&T) -> T>,
+    served: Mutex<VecDeque<Served<T>>>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self {
+            capacity: 0,
+            clone: None,
+            served: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl<T> History<T> {
+    pub(crate) fn new(capacity: usize, clone: fn(&T) -> T) -> Self {
+        Self {
+            capacity,
+            clone: Some(clone),
+            served: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Keep a copy of a value produced by the source of `generation`,
+    /// dropping the oldest one if full
+    pub(crate) fn record(&self, value: &T, generation: Generation) {
+        let Some(clone) = self.clone.filter(|_| self.capacity > 0) else {
+            return;
+        };
+
+        let mut served = self.served.lock().unwrap_or_else(PoisonError::into_inner);
+        if served.len() == self.capacity {
+            served.pop_front();
+        }
+        served.push_back(Served {
+            value: clone(value),
+            generation,
+            at: SystemTime::now(),
+        });
+    }
+
+    /// The last `n` values kept, oldest first
+    pub(crate) fn last(&self, n: usize) -> Vec<Served<T>> {
+        let Some(clone) = self.clone else {
+            return Vec::new();
+        };
+
+        let served = self.served.lock().unwrap_or_else(PoisonError::into_inner);
+        served
+            .iter()
+            .skip(served.len().saturating_sub(n))
+            .map(|served| served.copy(clone))
+            .collect()
+    }
+}
+
+/// Applies a [FailurePolicy], remembering what it needs to
+pub(crate) struct Recovery<T> {
+    policy: FailurePolicy,
+    clone: Option<fn(&T) -> T>,
+    /// The last value, along with the generation of the source producing it
+    last_good: Mutex<Option<(Generation, T)>>,
+}
+
+impl<T> Default for Recovery<T> {
+    fn default() -> Self {
+        Self {
+            policy: FailurePolicy::default(),
+            clone: None,
+            last_good: Mutex::new(None),
+        }
+    }
+}
+
+impl<T> Recovery<T> {
+    pub(crate) fn new(policy: FailurePolicy, clone: fn(&T) -> T) -> Self {
+        Self {
+            policy,
+            clone: Some(clone),
+            last_good: Mutex::new(None),
+        }
+    }
+
+    /// How long to wait before retrying, if the evaluation that failed after
+    /// `retries` retries should be retried
+    pub(crate) fn retry(&self, retries: usize) -> Option<Duration> {
+        match self.policy {
+            FailurePolicy::RetryN { attempts, backoff } if retries < attempts => Some(backoff),
+            _ => None,
+        }
+    }
+
+    /// Keep a copy of a successful value of the source of `generation`, if
+    /// it may be served later
+    pub(crate) fn succeeded(&self, value: &T, generation: Generation) {
+        if let (FailurePolicy::ServeLastGood, Some(clone)) = (self.policy, self.clone) {
+            *self
+                .last_good
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some((generation, clone(value)));
+        }
+    }
+
+    /// Build the response for an evaluation of the source of `generation`
+    /// that failed for good
+    pub(crate) fn failed(
+        &self,
+        source: &SourceHandle<T>,
+        generation: Generation,
+        error: Option<Error>,
+    ) -> Reply<T> {
+        let last_good = self
+            .last_good
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match (self.policy, self.clone, &*last_good) {
+            // Only a value of the current source will do
+            (FailurePolicy::ServeLastGood, Some(clone), Some((good, value)))
+                if *good == generation =>
+            {
+                return Reply::Value(clone(value));
+            }
+            (FailurePolicy::CloseChannel, _, _) => {
+                source.install(ValueSource::Cleared);
+                return Reply::Closed;
+            }
+            _ => {}
+        }
+
+        match error {
+            Some(error) => Reply::Failed(error),
+            None => Reply::NoSource,
+        }
+    }
+}